@@ -0,0 +1 @@
+ALTER TABLE score_sheet ADD COLUMN grid TEXT NOT NULL DEFAULT '';
@@ -0,0 +1 @@
+ALTER TABLE score_sheet ADD COLUMN hard_mode BOOLEAN NOT NULL DEFAULT FALSE;
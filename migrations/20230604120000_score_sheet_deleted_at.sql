@@ -0,0 +1,2 @@
+-- Deleted messages keep their score sheet for the audit trail
+ALTER TABLE score_sheet ADD COLUMN deleted_at INTEGER;
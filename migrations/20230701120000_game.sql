@@ -0,0 +1,38 @@
+-- Results of other daily games are tracked next to wordle, so both the days and the
+-- score sheets are keyed by game. SQLite can not alter constraints, hence the rebuild.
+ALTER TABLE score_sheet RENAME TO score_sheet_old;
+ALTER TABLE daily RENAME TO daily_old;
+
+CREATE TABLE daily (
+	id INTEGER NOT NULL,
+	game TEXT NOT NULL DEFAULT 'wordle',
+	gold INTEGER,
+	silver INTEGER,
+	bronze INTEGER,
+	PRIMARY KEY(game, id)
+);
+
+INSERT INTO daily (id, game, gold, silver, bronze)
+SELECT id, 'wordle', gold, silver, bronze FROM daily_old;
+
+CREATE TABLE score_sheet (
+	id INTEGER PRIMARY KEY,
+	msg_id INTEGER NOT NULL,
+	day INTEGER NOT NULL,
+	player_id INTEGER NOT NULL,
+	score INTEGER NOT NULL,
+	cup_number TEXT NOT NULL,
+	grid TEXT NOT NULL DEFAULT '',
+	hard_mode BOOLEAN NOT NULL DEFAULT FALSE,
+	deleted_at INTEGER,
+	game TEXT NOT NULL DEFAULT 'wordle',
+	FOREIGN KEY(player_id) REFERENCES player(id),
+	FOREIGN KEY(game, day) REFERENCES daily(game, id),
+	UNIQUE(player_id, game, day)
+);
+
+INSERT INTO score_sheet (id, msg_id, day, player_id, score, cup_number, grid, hard_mode, deleted_at, game)
+SELECT id, msg_id, day, player_id, score, cup_number, grid, hard_mode, deleted_at, 'wordle' FROM score_sheet_old;
+
+DROP TABLE score_sheet_old;
+DROP TABLE daily_old;
@@ -0,0 +1,7 @@
+-- The scoring rule of a cup, cups without a row use the default rule
+CREATE TABLE scoring_rule (
+	guild_id INTEGER NOT NULL,
+	cup_number TEXT NOT NULL,
+	rule TEXT NOT NULL,
+	PRIMARY KEY(guild_id, cup_number)
+);
@@ -0,0 +1,10 @@
+-- Glicko-2 rating of a player after every day they played, rebuilt from the score sheets
+CREATE TABLE rating_history (
+	player_id INTEGER NOT NULL,
+	date TEXT NOT NULL,
+	rating REAL NOT NULL,
+	deviation REAL NOT NULL,
+	volatility REAL NOT NULL,
+	PRIMARY KEY(player_id, date),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
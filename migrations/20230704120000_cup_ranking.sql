@@ -0,0 +1,7 @@
+-- How a guild ranks its cups when announcing the winner, guilds without a row rank by sum
+CREATE TABLE cup_ranking (
+	guild_id INTEGER PRIMARY KEY,
+	ranking TEXT NOT NULL,
+	min_games INTEGER NOT NULL DEFAULT 0,
+	shrinkage BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,60 @@
+-- Cups become seasons with an explicit first and last day, instead of the year and
+-- month squashed into a string. The monthly cups so far become monthly seasons.
+CREATE TABLE season (
+	id INTEGER PRIMARY KEY,
+	name TEXT NOT NULL UNIQUE,
+	start_date TEXT NOT NULL,
+	end_date TEXT NOT NULL
+);
+
+INSERT INTO season (name, start_date, end_date)
+SELECT year || '/' || month, start_date, date(start_date, '+1 month', '-1 day')
+FROM (
+	SELECT DISTINCT
+		substr(cup_number, 1, 4) AS year,
+		CAST(substr(cup_number, 5) AS INTEGER) AS month,
+		printf('%s-%02d-01', substr(cup_number, 1, 4), CAST(substr(cup_number, 5) AS INTEGER)) AS start_date
+	FROM (SELECT cup_number FROM score_sheet UNION SELECT cup_number FROM scoring_rule)
+)
+ORDER BY start_date;
+
+ALTER TABLE score_sheet RENAME TO score_sheet_old;
+ALTER TABLE scoring_rule RENAME TO scoring_rule_old;
+
+CREATE TABLE score_sheet (
+	id INTEGER PRIMARY KEY,
+	msg_id INTEGER NOT NULL,
+	day INTEGER NOT NULL,
+	player_id INTEGER NOT NULL,
+	score INTEGER NOT NULL,
+	season_id INTEGER NOT NULL,
+	grid TEXT NOT NULL DEFAULT '',
+	hard_mode BOOLEAN NOT NULL DEFAULT FALSE,
+	deleted_at INTEGER,
+	game TEXT NOT NULL DEFAULT 'wordle',
+	FOREIGN KEY(player_id) REFERENCES player(id),
+	FOREIGN KEY(game, day) REFERENCES daily(game, id),
+	FOREIGN KEY(season_id) REFERENCES season(id),
+	UNIQUE(player_id, game, day)
+);
+
+INSERT INTO score_sheet (id, msg_id, day, player_id, score, season_id, grid, hard_mode, deleted_at, game)
+SELECT score_sheet_old.id, msg_id, day, player_id, score, season.id, grid, hard_mode, deleted_at, game
+FROM score_sheet_old
+JOIN season ON season.name = substr(cup_number, 1, 4) || '/' || CAST(substr(cup_number, 5) AS INTEGER);
+
+CREATE TABLE scoring_rule (
+	guild_id INTEGER NOT NULL,
+	season_id INTEGER NOT NULL,
+	rule TEXT NOT NULL,
+	PRIMARY KEY(guild_id, season_id),
+	FOREIGN KEY(season_id) REFERENCES season(id)
+);
+
+INSERT INTO scoring_rule (guild_id, season_id, rule)
+SELECT guild_id, season.id, rule
+FROM scoring_rule_old
+JOIN season ON season.name = substr(cup_number, 1, 4) || '/' || CAST(substr(cup_number, 5) AS INTEGER);
+
+DROP TABLE score_sheet_old;
+DROP TABLE scoring_rule_old;
@@ -0,0 +1,6 @@
+-- Monthly seasons were named like "2023/1", which neither sorts nor reads well next to
+-- "2023/11". They are renamed to the zero padded "2023-01".
+UPDATE season
+SET name = printf('%s-%02d', substr(name, 1, 4), CAST(substr(name, 6) AS INTEGER))
+WHERE name GLOB '[0-9][0-9][0-9][0-9]/[0-9]*'
+	AND start_date = printf('%s-%02d-01', substr(name, 1, 4), CAST(substr(name, 6) AS INTEGER));
@@ -0,0 +1,22 @@
+-- Every announced cup winner, so that a restart neither skips nor repeats an announcement.
+-- The cup is the name of a game, or omni for the omnicup. Cups without a winner have no winner.
+CREATE TABLE announcement (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	winner INTEGER,
+	announced_at INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup),
+	FOREIGN KEY(season_id) REFERENCES season(id),
+	FOREIGN KEY(winner) REFERENCES player(id)
+);
+
+-- The seasons that already ended were announced when they did
+INSERT INTO announcement (season_id, cup, announced_at)
+SELECT season.id, cup.name, CAST(strftime('%s', 'now') AS INTEGER)
+FROM season, (
+	SELECT 'wordle' AS name UNION ALL SELECT 'connections' UNION ALL SELECT 'strands'
+	UNION ALL SELECT 'nerdle' UNION ALL SELECT 'quordle' UNION ALL SELECT 'octordle'
+	UNION ALL SELECT 'worldle' UNION ALL SELECT 'framed' UNION ALL SELECT 'ordel'
+	UNION ALL SELECT 'omni'
+) AS cup
+WHERE season.end_date < date('now');
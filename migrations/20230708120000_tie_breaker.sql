@@ -0,0 +1,34 @@
+-- How a guild separates players tied for a cup win, as a comma separated list of tie
+-- breakers in order. Guilds without a row let tied players share the win.
+CREATE TABLE tie_breaker (
+	guild_id INTEGER PRIMARY KEY,
+	tie_breakers TEXT NOT NULL
+);
+
+-- Shared wins have several winners, so they get a table of their own
+ALTER TABLE announcement RENAME TO announcement_old;
+
+CREATE TABLE announcement (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	announced_at INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup),
+	FOREIGN KEY(season_id) REFERENCES season(id)
+);
+
+CREATE TABLE announcement_winner (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	player_id INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup, player_id),
+	FOREIGN KEY(season_id, cup) REFERENCES announcement(season_id, cup),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
+
+INSERT INTO announcement (season_id, cup, announced_at)
+SELECT season_id, cup, announced_at FROM announcement_old;
+
+INSERT INTO announcement_winner (season_id, cup, player_id)
+SELECT season_id, cup, winner FROM announcement_old WHERE winner IS NOT NULL;
+
+DROP TABLE announcement_old;
@@ -0,0 +1,16 @@
+-- The final standings of every closed cup, the hall of fame. The cup is the name of a game,
+-- or omni for the omnicup. Players tied after the tie breakers share a placement, and the
+-- medals are the daily medals won during the cup.
+CREATE TABLE cup_result (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	player_id INTEGER NOT NULL,
+	placement INTEGER NOT NULL,
+	points INTEGER NOT NULL,
+	gold INTEGER NOT NULL,
+	silver INTEGER NOT NULL,
+	bronze INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup, player_id),
+	FOREIGN KEY(season_id) REFERENCES season(id),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
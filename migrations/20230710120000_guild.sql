@@ -0,0 +1,150 @@
+-- Every guild the bot is in plays on its own: its own days and medals, its own seasons and
+-- its own ratings. Score sheets also remember the channel they were posted in. Tables keyed
+-- by season are scoped through the season. Everything so far was played in one guild and
+-- one channel. SQLite can not alter constraints, hence the rebuild of everything that
+-- refers to the days or the seasons.
+ALTER TABLE score_sheet RENAME TO score_sheet_old;
+ALTER TABLE daily RENAME TO daily_old;
+ALTER TABLE season RENAME TO season_old;
+ALTER TABLE scoring_rule RENAME TO scoring_rule_old;
+ALTER TABLE announcement_winner RENAME TO announcement_winner_old;
+ALTER TABLE announcement RENAME TO announcement_old;
+ALTER TABLE cup_result RENAME TO cup_result_old;
+ALTER TABLE rating_history RENAME TO rating_history_old;
+ALTER TABLE audit_log RENAME TO audit_log_old;
+
+CREATE TABLE season (
+	id INTEGER PRIMARY KEY,
+	guild_id INTEGER NOT NULL,
+	name TEXT NOT NULL,
+	start_date TEXT NOT NULL,
+	end_date TEXT NOT NULL,
+	UNIQUE(guild_id, name)
+);
+
+INSERT INTO season (id, guild_id, name, start_date, end_date)
+SELECT id, 486522741395161108, name, start_date, end_date FROM season_old;
+
+CREATE TABLE daily (
+	guild_id INTEGER NOT NULL,
+	id INTEGER NOT NULL,
+	game TEXT NOT NULL,
+	gold INTEGER,
+	silver INTEGER,
+	bronze INTEGER,
+	PRIMARY KEY(guild_id, game, id)
+);
+
+INSERT INTO daily (guild_id, id, game, gold, silver, bronze)
+SELECT 486522741395161108, id, game, gold, silver, bronze FROM daily_old;
+
+CREATE TABLE score_sheet (
+	id INTEGER PRIMARY KEY,
+	msg_id INTEGER NOT NULL,
+	guild_id INTEGER NOT NULL,
+	channel_id INTEGER NOT NULL,
+	day INTEGER NOT NULL,
+	player_id INTEGER NOT NULL,
+	score INTEGER NOT NULL,
+	season_id INTEGER NOT NULL,
+	grid TEXT NOT NULL DEFAULT '',
+	hard_mode BOOLEAN NOT NULL DEFAULT FALSE,
+	deleted_at INTEGER,
+	game TEXT NOT NULL,
+	FOREIGN KEY(player_id) REFERENCES player(id),
+	FOREIGN KEY(guild_id, game, day) REFERENCES daily(guild_id, game, id),
+	FOREIGN KEY(season_id) REFERENCES season(id),
+	UNIQUE(guild_id, player_id, game, day)
+);
+
+INSERT INTO score_sheet (id, msg_id, guild_id, channel_id, day, player_id, score, season_id, grid, hard_mode, deleted_at, game)
+SELECT id, msg_id, 486522741395161108, 938727764037619712, day, player_id, score, season_id, grid, hard_mode, deleted_at, game
+FROM score_sheet_old;
+
+CREATE TABLE scoring_rule (
+	guild_id INTEGER NOT NULL,
+	season_id INTEGER NOT NULL,
+	rule TEXT NOT NULL,
+	PRIMARY KEY(guild_id, season_id),
+	FOREIGN KEY(season_id) REFERENCES season(id)
+);
+
+INSERT INTO scoring_rule (guild_id, season_id, rule)
+SELECT guild_id, season_id, rule FROM scoring_rule_old;
+
+CREATE TABLE announcement (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	announced_at INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup),
+	FOREIGN KEY(season_id) REFERENCES season(id)
+);
+
+INSERT INTO announcement (season_id, cup, announced_at)
+SELECT season_id, cup, announced_at FROM announcement_old;
+
+CREATE TABLE announcement_winner (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	player_id INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup, player_id),
+	FOREIGN KEY(season_id, cup) REFERENCES announcement(season_id, cup),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
+
+INSERT INTO announcement_winner (season_id, cup, player_id)
+SELECT season_id, cup, player_id FROM announcement_winner_old;
+
+CREATE TABLE cup_result (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	player_id INTEGER NOT NULL,
+	placement INTEGER NOT NULL,
+	points INTEGER NOT NULL,
+	gold INTEGER NOT NULL,
+	silver INTEGER NOT NULL,
+	bronze INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup, player_id),
+	FOREIGN KEY(season_id) REFERENCES season(id),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
+
+INSERT INTO cup_result (season_id, cup, player_id, placement, points, gold, silver, bronze)
+SELECT season_id, cup, player_id, placement, points, gold, silver, bronze FROM cup_result_old;
+
+CREATE TABLE rating_history (
+	guild_id INTEGER NOT NULL,
+	player_id INTEGER NOT NULL,
+	date TEXT NOT NULL,
+	rating REAL NOT NULL,
+	deviation REAL NOT NULL,
+	volatility REAL NOT NULL,
+	PRIMARY KEY(guild_id, player_id, date),
+	FOREIGN KEY(player_id) REFERENCES player(id)
+);
+
+INSERT INTO rating_history (guild_id, player_id, date, rating, deviation, volatility)
+SELECT 486522741395161108, player_id, date, rating, deviation, volatility FROM rating_history_old;
+
+CREATE TABLE audit_log (
+	id INTEGER PRIMARY KEY,
+	guild_id INTEGER NOT NULL,
+	timestamp INTEGER NOT NULL,
+	actor_id INTEGER NOT NULL,
+	action TEXT NOT NULL,
+	msg_id INTEGER,
+	detail TEXT NOT NULL
+);
+
+INSERT INTO audit_log (id, guild_id, timestamp, actor_id, action, msg_id, detail)
+SELECT id, 486522741395161108, timestamp, actor_id, action, msg_id, detail FROM audit_log_old;
+
+DROP TABLE announcement_winner_old;
+DROP TABLE announcement_old;
+DROP TABLE cup_result_old;
+DROP TABLE scoring_rule_old;
+DROP TABLE score_sheet_old;
+DROP TABLE season_old;
+DROP TABLE daily_old;
+DROP TABLE rating_history_old;
+DROP TABLE audit_log_old;
@@ -0,0 +1,13 @@
+-- The settings of a guild, set with /config. Guilds without a row use the defaults, the
+-- channels and the admin role are optional.
+CREATE TABLE guild_config (
+	guild_id INTEGER PRIMARY KEY,
+	tracked_channel_id INTEGER,
+	announcement_channel_id INTEGER,
+	scoring_rule TEXT NOT NULL,
+	season_period TEXT NOT NULL,
+	time_zone TEXT NOT NULL,
+	language TEXT NOT NULL,
+	reactions BOOLEAN NOT NULL,
+	admin_role_id INTEGER
+);
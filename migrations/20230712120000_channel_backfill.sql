@@ -0,0 +1,6 @@
+-- The newest message of every channel that has been read, backfills continue after it
+CREATE TABLE channel_backfill (
+	channel_id INTEGER PRIMARY KEY,
+	guild_id INTEGER NOT NULL,
+	last_msg_id INTEGER NOT NULL
+);
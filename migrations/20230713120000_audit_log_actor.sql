@@ -0,0 +1,19 @@
+-- Not every action has a known actor, e.g. Discord does not tell who deleted a message.
+-- Deletions were recorded as done by the author of the message, which they need not be.
+ALTER TABLE audit_log RENAME TO audit_log_old;
+
+CREATE TABLE audit_log (
+	id INTEGER PRIMARY KEY,
+	guild_id INTEGER NOT NULL,
+	timestamp INTEGER NOT NULL,
+	actor_id INTEGER,
+	action TEXT NOT NULL,
+	msg_id INTEGER,
+	detail TEXT NOT NULL
+);
+
+INSERT INTO audit_log (id, guild_id, timestamp, actor_id, action, msg_id, detail)
+SELECT id, guild_id, timestamp, CASE WHEN action = 'delete' THEN NULL ELSE actor_id END, action, msg_id, detail
+FROM audit_log_old;
+
+DROP TABLE audit_log_old;
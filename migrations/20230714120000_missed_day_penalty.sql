@@ -0,0 +1,3 @@
+-- The points a player loses for every day of a cup they skipped. Skipped days do not count
+-- unless it is set.
+ALTER TABLE guild_config ADD COLUMN missed_day_penalty INTEGER;
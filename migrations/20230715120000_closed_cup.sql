@@ -0,0 +1,12 @@
+-- Every cup whose final standings are stored, also the ones nobody played, so a closed
+-- cup is never derived again.
+CREATE TABLE closed_cup (
+	season_id INTEGER NOT NULL,
+	cup TEXT NOT NULL,
+	closed_at INTEGER NOT NULL,
+	PRIMARY KEY(season_id, cup),
+	FOREIGN KEY(season_id) REFERENCES season(id)
+);
+
+INSERT INTO closed_cup (season_id, cup, closed_at)
+SELECT DISTINCT season_id, cup, strftime('%s', 'now') FROM cup_result;
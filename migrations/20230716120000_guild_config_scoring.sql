@@ -0,0 +1,5 @@
+-- Settings that used to be read from the environment for all guilds. Guilds that skip the
+-- grid validation accept any result, the hard mode bonus is added to every solved game
+-- played in hard mode.
+ALTER TABLE guild_config ADD COLUMN grid_validation BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE guild_config ADD COLUMN hard_mode_bonus INTEGER NOT NULL DEFAULT 0;
@@ -1,50 +1,91 @@
-use std::sync::Arc;
+use std::{collections::BTreeSet, fmt::Display, sync::Arc, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use log::{debug, error, info};
 use serenity::{
-    futures::StreamExt,
-    model::prelude::{ChannelId, Message, ReactionType},
+    model::prelude::{ChannelId, GuildId, Message, MessageId, ReactionType},
     prelude::{Context, RwLock},
 };
 
 use crate::{
+    command::admin::Progress,
+    config::GuildConfig,
     database::CachedDatabase as Database,
-    parser,
-    utils::{cup_number_from_unixtime, recalcualate_high_scores},
+    parser::{self, Game, GameResult},
+    scoresheet::Scoresheet,
+    scoring::Scoring,
+    season::Season,
+    validation::{validate_day, Rejection},
     Placement,
 };
 
+// Messages per request when backfilling, the most Discord allows
+const PAGE_SIZE: u64 = 100;
+// How many times a page is requested before the backfill gives up
+const FETCH_ATTEMPTS: u64 = 3;
+
+/// What a backfill came across. Skipped messages are not results or already counted,
+/// rejected results did not pass validation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Backfill {
+    pub(crate) parsed: usize,
+    pub(crate) skipped: usize,
+    pub(crate) rejected: usize,
+}
+
+impl Display for Backfill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} nya resultat, {} överhoppade och {} underkända meddelanden",
+            self.parsed, self.skipped, self.rejected
+        )
+    }
+}
+
 pub(crate) struct Bot {
     pub database: Arc<RwLock<Database>>,
 }
 
 impl Bot {
+    // Medals are awarded in the channel every result was posted in, unless the guild turned
+    // reactions off
     pub(crate) async fn set_medals(
         &self,
+        guild_id: i64,
+        game: Game,
         day: i64,
-        channel_id: ChannelId,
         http: &Context,
     ) -> Result<()> {
         // playerId, msgId, score
         let database = self.database.read().await;
+        if !database.get_config(guild_id).await?.reactions {
+            return Ok(());
+        }
         for (p, medalists) in [
             (
                 Placement::Gold,
-                database.get_gold_medalist(Some(day)).await?,
+                database
+                    .get_gold_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
             (
                 Placement::Silver,
-                database.get_silver_medalist(Some(day)).await?,
+                database
+                    .get_silver_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
             (
                 Placement::Bronze,
-                database.get_bronze_medalist(Some(day)).await?,
+                database
+                    .get_bronze_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
         ] {
             let Some(medalists) = medalists else {continue;};
             for medalist in medalists {
-                channel_id
+                medalist
+                    .channel_id()
                     .create_reaction(
                         http,
                         medalist.message_id(),
@@ -56,25 +97,42 @@ impl Bot {
         Ok(())
     }
 
-    async fn clear_medals(&self, day: i64, channel_id: ChannelId, http: &Context) -> Result<()> {
+    async fn clear_medals(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: i64,
+        http: &Context,
+    ) -> Result<()> {
+        // Like set_medals, guilds without reactions are left alone
         let database = self.database.read().await;
+        if !database.get_config(guild_id).await?.reactions {
+            return Ok(());
+        }
         for (p, medalists) in [
             (
                 Placement::Gold,
-                database.get_gold_medalist(Some(day)).await?,
+                database
+                    .get_gold_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
             (
                 Placement::Silver,
-                database.get_silver_medalist(Some(day)).await?,
+                database
+                    .get_silver_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
             (
                 Placement::Bronze,
-                database.get_bronze_medalist(Some(day)).await?,
+                database
+                    .get_bronze_medalist(guild_id, game, Some(day))
+                    .await?,
             ),
         ] {
             let Some(medalists) = medalists else {continue;};
             for medalist in medalists {
-                channel_id
+                medalist
+                    .channel_id()
                     .delete_reaction(
                         http,
                         medalist.message_id(),
@@ -86,67 +144,462 @@ impl Bot {
         }
         Ok(())
     }
-    async fn new_score_sheet(&self, msg: &Message) -> Result<()> {
-        let cup_number = cup_number_from_unixtime(msg.timestamp.unix_timestamp());
-        let player_id = msg.author.id.0 as i64;
-        let msg_id = msg.id.0 as i64;
-        let (day, score) = parser::parse_msg(&msg.content)?;
-        let database = self.database.write().await;
-        // Create new player if not exists
-        database.new_player(player_id).await?;
-        // TODO: Is there a better place to do this to avoid runtime error if this is not executed first?
-        database.new_daily(day).await?;
-        debug!("Day: {}, Score: {}, Cup number: {}", day, score, cup_number);
-        self.new_daily_score(Some(day), score).await?;
-        database
-            .new_score_sheet(msg_id, day, player_id, score, cup_number)
+    // Parses the message and checks that the result is believable, as far as the guild checks
+    fn parse_and_validate(msg: &Message, config: &GuildConfig) -> Result<GameResult> {
+        let parser = parser::find_parser(&msg.content).context("Not a share message")?;
+        debug!("Parsing {} result {}", parser.game(), msg.id);
+        let result = parser.parse(&msg.content)?;
+        // Games not numbered by the calendar can not be checked against the date
+        if let Some(epoch) = parser.epoch() {
+            validate_day(&result, epoch, msg.timestamp.unix_timestamp())?;
+        }
+        if config.grid_validation {
+            parser.validate(&result)?;
+        }
+        Ok(result)
+    }
+
+    async fn new_score_sheet(&self, msg: &Message) -> Result<bool> {
+        let config = self
+            .database
+            .read()
+            .await
+            .get_config(guild_of(msg)?)
             .await?;
-        Ok(())
+        let result = Self::parse_and_validate(msg, &config)?;
+        self.store_score_sheet(msg, &result).await
+    }
+
+    // Whether the result was stored, it is not if the player already has one for the day
+    async fn store_score_sheet(&self, msg: &Message, result: &GameResult) -> Result<bool> {
+        let guild_id = guild_of(msg)?;
+        self.database
+            .write()
+            .await
+            .ingest(
+                msg.id.0 as i64,
+                guild_id,
+                msg.channel_id.0 as i64,
+                msg.author.id.0 as i64,
+                msg.timestamp.unix_timestamp(),
+                result,
+            )
+            .await
     }
 
-    pub(crate) async fn read_old_messages(&self, channel_id: ChannelId, http: &Context) {
-        debug!("Reading old messages");
-        let mut msg_count: i64 = 0;
-        let mut messages = channel_id.messages_iter(http).boxed();
-        while let Some(msg_result) = messages.next().await {
-            let msg = match msg_result {
-                Err(e) => {
-                    error!("{e}");
-                    return;
+    // Counts the results posted in the channel since it was last read, or its whole history.
+    // The channel is marked as read after every page, so that an interrupted backfill resumes
+    // where it stopped. Channels other than the tracked one of the guild are not read.
+    pub(crate) async fn backfill(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        from_start: bool,
+        http: &Context,
+        progress: Option<&Progress<'_>>,
+    ) -> Result<Backfill> {
+        let guild = guild_id.0 as i64;
+        let mut backfill = Backfill::default();
+        let (config, mark) = {
+            let database = self.database.read().await;
+            let mark = database.get_backfill_mark(channel_id.0 as i64).await?;
+            (database.get_config(guild).await?, mark)
+        };
+        if !config.is_tracked(channel_id.0 as i64) {
+            return Ok(backfill);
+        }
+        let mut after = match from_start {
+            true => 0,
+            false => mark.unwrap_or(0) as u64,
+        };
+        debug!("Backfilling channel {channel_id} after message {after}");
+        // The medals of every day with new results are moved once all of them are counted
+        let mut days = BTreeSet::new();
+        loop {
+            let mut messages = self.fetch_page(channel_id, after, http).await?;
+            if messages.is_empty() {
+                break;
+            }
+            messages.sort_by_key(|msg| msg.id);
+            for mut msg in messages {
+                after = after.max(msg.id.0);
+                // Fetched messages do not know their guild
+                msg.guild_id = Some(guild_id);
+                if parser::find_parser(&msg.content).is_none()
+                    || self
+                        .database
+                        .read()
+                        .await
+                        .is_counted(msg.id.0 as i64)
+                        .await?
+                {
+                    backfill.skipped += 1;
+                    continue;
                 }
-                Ok(msg) => msg,
-            };
-            if msg.content.starts_with("Wordle") {
-                msg_count += 1;
-                _ = self.new_score_sheet(&msg).await;
+                let result = match Self::parse_and_validate(&msg, &config) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        debug!("Rejected result {} in backfill: {e}", msg.id);
+                        backfill.rejected += 1;
+                        continue;
+                    }
+                };
+                if days.insert((result.game, result.day)) {
+                    self.clear_medals(guild, result.game, result.day, http)
+                        .await?;
+                }
+                // A second result of the same day by the same player is not stored
+                match self.store_score_sheet(&msg, &result).await? {
+                    true => backfill.parsed += 1,
+                    false => backfill.skipped += 1,
+                }
+            }
+            self.database
+                .write()
+                .await
+                .set_backfill_mark(guild, channel_id.0 as i64, after as i64)
+                .await?;
+            if let Some(progress) = progress {
+                progress
+                    .report(format!("Läser in <#{}>: {backfill}…", channel_id.0))
+                    .await?;
+            }
+        }
+        for (game, day) in days {
+            self.set_medals(guild, game, day, http).await?;
+        }
+        debug!("Backfilled channel {channel_id}: {backfill:?}");
+        self.database.write().await.update_cache(guild).await?;
+        Ok(backfill)
+    }
+
+    // The page of messages following the given one, retried a few times before giving up
+    async fn fetch_page(
+        &self,
+        channel_id: ChannelId,
+        after: u64,
+        http: &Context,
+    ) -> Result<Vec<Message>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let page = channel_id
+                .messages(http, |retriever| {
+                    retriever.after(MessageId(after)).limit(PAGE_SIZE)
+                })
+                .await;
+            match page {
+                Ok(messages) => return Ok(messages),
+                Err(e) if attempt < FETCH_ATTEMPTS => {
+                    error!("Fetching messages of {channel_id} failed, retrying: {e}");
+                    tokio::time::sleep(Duration::from_secs(attempt)).await;
+                }
+                Err(e) => return Err(e.into()),
             }
         }
-        info!("Old messages read: {msg_count}");
     }
 
-    async fn new_daily_score(&self, day: Option<i64>, score: i64) -> Result<()> {
-        if score == 0 {
+    pub(crate) async fn handle_wordle_message(&self, msg: &Message, ctx: &Context) -> Result<()> {
+        let guild_id = guild_of(msg)?;
+        let config = self.database.read().await.get_config(guild_id).await?;
+        if !config.is_tracked(msg.channel_id.0 as i64) {
             return Ok(());
         }
-        let database = self.database.write().await;
-        let day = day.unwrap_or(database.get_daily_day().await?);
-        let high_scores = database.get_daily_high_scores(day).await?;
-        let high_scores = recalcualate_high_scores(high_scores, score);
-        database
-            .update_daily(day, high_scores[0], high_scores[1], high_scores[2])
-            .await
+        let result = parser::parse_msg(&msg.content)?;
+        let (game, day) = (result.game, result.day);
+        self.clear_medals(guild_id, game, day, ctx).await?;
+        let inserted = self.new_score_sheet(msg).await;
+        self.set_medals(guild_id, game, day, ctx).await?;
+        if let Err(e) = inserted {
+            return match e.downcast::<Rejection>() {
+                Ok(rejection) => self.reject(msg, ctx, &rejection).await,
+                Err(e) => Err(e),
+            };
+        }
+        self.database.write().await.update_cache(guild_id).await?;
+        Ok(())
     }
 
-    pub(crate) async fn handle_wordle_message(
+    // Lets the player know why their result was not counted
+    async fn reject(&self, msg: &Message, ctx: &Context, rejection: &Rejection) -> Result<()> {
+        info!("Rejected result {}: {rejection}", msg.id);
+        let guild_id = guild_of(msg)?;
+        let config = self.database.read().await.get_config(guild_id).await?;
+        if config.reactions {
+            msg.react(ctx, ReactionType::Unicode("❌".to_string()))
+                .await?;
+        }
+        msg.reply(ctx, format!("Resultatet räknas inte, {rejection}"))
+            .await?;
+        Ok(())
+    }
+
+    // Re-evaluates an edited message, moving medals if the result changed. Returns whether a
+    // result was counted, changed or retracted.
+    pub(crate) async fn handle_wordle_edit(&self, msg: &Message, ctx: &Context) -> Result<bool> {
+        let msg_id = msg.id.0 as i64;
+        let Some(old) = self.database.read().await.get_score_sheet(msg_id).await? else {
+            // Never counted, so the edit might have turned it into a result
+            if parser::find_parser(&msg.content).is_some() {
+                self.handle_wordle_message(msg, ctx).await?;
+                let counted = self.database.read().await.get_score_sheet(msg_id).await?;
+                return Ok(counted.is_some());
+            }
+            return Ok(false);
+        };
+        let guild_id = old.guild_id;
+        let old_game = old.game();
+        let config = self.database.read().await.get_config(guild_id).await?;
+        let result = match Self::parse_and_validate(msg, &config) {
+            // A player only has one result a day, the edit may not move it onto another one
+            Ok(result) => {
+                let played = self
+                    .database
+                    .read()
+                    .await
+                    .get_player_score_sheet(guild_id, old.player_id, result.game, result.day)
+                    .await?;
+                match played.filter(|sheet| sheet.msg_id != msg_id) {
+                    Some(_) => Err(Rejection::AlreadyPlayed { day: result.day }.into()),
+                    None => Ok(result),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        let result = match result {
+            Ok(result) => result,
+            // The message no longer shows the counted result, so it stops counting
+            Err(e) => {
+                self.clear_medals(guild_id, old_game, old.day, ctx).await?;
+                let retracted = self.retract_edit(msg, guild_id, &e.to_string()).await;
+                self.set_medals(guild_id, old_game, old.day, ctx).await?;
+                retracted?;
+                match e.downcast::<Rejection>() {
+                    Ok(rejection) => self.reject(msg, ctx, &rejection).await?,
+                    Err(e) => return Err(e),
+                }
+                return Ok(true);
+            }
+        };
+        if (old_game, old.day, old.score, old.hard_mode)
+            == (result.game, result.day, result.score, result.hard_mode)
+            && old.grid == result.grid()
+        {
+            return Ok(false);
+        }
+        let days = match (old_game, old.day) == (result.game, result.day) {
+            true => vec![(old_game, old.day)],
+            false => vec![(old_game, old.day), (result.game, result.day)],
+        };
+        for (game, day) in &days {
+            self.clear_medals(guild_id, *game, *day, ctx).await?;
+        }
+        // The medals are put back whether or not the edit could be stored
+        let stored = self.store_edit(msg, &old, &result, &days).await;
+        for (game, day) in &days {
+            self.set_medals(guild_id, *game, *day, ctx).await?;
+        }
+        stored?;
+        Ok(true)
+    }
+
+    // Stops counting the result of an edited message, along with its audit entry
+    async fn retract_edit(&self, msg: &Message, guild_id: i64, reason: &str) -> Result<()> {
+        let msg_id = msg.id.0 as i64;
+        let mut database = self.database.write().await;
+        let mut work = database.begin().await?;
+        work.retract_score_sheet(msg_id).await?;
+        work.new_audit_entry(
+            guild_id,
+            Some(msg.author.id.0 as i64),
+            "edit rejected",
+            Some(msg_id),
+            reason,
+        )
+        .await?;
+        work.commit().await?;
+        database.update_cache(guild_id).await
+    }
+
+    // Replaces the result of an edited message and recounts the days it moved between
+    async fn store_edit(
         &self,
         msg: &Message,
+        old: &Scoresheet,
+        result: &GameResult,
+        days: &[(Game, i64)],
+    ) -> Result<()> {
+        let (msg_id, guild_id, old_game) = (old.msg_id, old.guild_id, old.game());
+        let mut database = self.database.write().await;
+        let mut work = database.begin().await?;
+        work.new_daily(guild_id, result.game, result.day).await?;
+        work.update_score_sheet(msg_id, result).await?;
+        for (game, day) in days {
+            work.recompute_daily(guild_id, *game, *day).await?;
+        }
+        let detail = format!(
+            "{old_game} dag {} {} -> {} dag {} {}",
+            old.day,
+            old_game.parser().describe_score(old.score),
+            result.game,
+            result.day,
+            result.game.parser().describe_score(result.score)
+        );
+        work.new_audit_entry(
+            guild_id,
+            Some(msg.author.id.0 as i64),
+            "edit",
+            Some(msg_id),
+            &detail,
+        )
+        .await?;
+        work.commit().await?;
+        // Readers never see the stored edit with the leaders of before
+        database.update_cache(guild_id).await?;
+        info!("Score sheet {msg_id} edited: {detail}");
+        Ok(())
+    }
+
+    // Rebuilds the high scores of a day from its score sheets and moves the medals to match.
+    // The ratings are replayed as well, they otherwise only change at midnight.
+    pub(crate) async fn recompute_day(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: i64,
         ctx: &Context,
     ) -> Result<()> {
-        let (day, _) = parser::parse_msg(&msg.content)?;
-        self.clear_medals(day, msg.channel_id, ctx).await?;
-        self.new_score_sheet(msg).await?;
-        self.set_medals(day, msg.channel_id, ctx).await?;
-        self.database.write().await.update_cache().await?;
+        self.clear_medals(guild_id, game, day, ctx).await?;
+        {
+            let database = self.database.write().await;
+            let mut work = database.begin().await?;
+            work.recompute_daily(guild_id, game, day).await?;
+            work.commit().await?;
+        }
+        self.set_medals(guild_id, game, day, ctx).await?;
+        let mut database = self.database.write().await;
+        database.recompute_ratings(guild_id).await?;
+        database.update_cache(guild_id).await?;
         Ok(())
     }
+
+    // Stops counting a result whose message is kept, e.g. a cheated one. Returns the voided
+    // result, unless there is no such result in the guild.
+    pub(crate) async fn void_score(
+        &self,
+        guild_id: i64,
+        msg_id: i64,
+        ctx: &Context,
+    ) -> Result<Option<Scoresheet>> {
+        let sheet = self.database.read().await.get_score_sheet(msg_id).await?;
+        let Some(sheet) = sheet.filter(|sheet| sheet.guild_id == guild_id) else {
+            return Ok(None);
+        };
+        let game = sheet.game();
+        // The medals are cleared while the sheet still holds one
+        self.clear_medals(guild_id, game, sheet.day, ctx).await?;
+        {
+            let database = self.database.write().await;
+            let mut work = database.begin().await?;
+            work.retract_score_sheet(msg_id).await?;
+            work.commit().await?;
+        }
+        self.set_medals(guild_id, game, sheet.day, ctx).await?;
+        self.database.write().await.update_cache(guild_id).await?;
+        Ok(Some(sheet))
+    }
+
+    // Moves every result of a player to another and recounts the guild. Returns how many
+    // results were moved and how many were voided for being played by both.
+    pub(crate) async fn merge_players(
+        &self,
+        guild_id: i64,
+        from: i64,
+        into: i64,
+    ) -> Result<(u64, u64)> {
+        let mut database = self.database.write().await;
+        let merged = database.merge_players(guild_id, from, into).await?;
+        database.recompute_all_dailies(guild_id).await?;
+        database.recompute_ratings(guild_id).await?;
+        database.update_cache(guild_id).await?;
+        Ok(merged)
+    }
+
+    // Retracts the results of deleted messages and re-awards the medals of their days.
+    // Returns the channels the retracted results were posted in.
+    pub(crate) async fn handle_deletions(
+        &self,
+        msg_ids: &[MessageId],
+        ctx: &Context,
+    ) -> Result<BTreeSet<ChannelId>> {
+        let mut days = BTreeSet::new();
+        let mut channels = BTreeSet::new();
+        {
+            let database = self.database.write().await;
+            let mut work = database.begin().await?;
+            for msg_id in msg_ids {
+                let Some(sheet) = work.delete_score_sheet(msg_id.0 as i64).await? else {
+                    continue;
+                };
+                let game = sheet.game();
+                // Discord does not tell who deleted the message, the author or a moderator
+                let detail = format!(
+                    "{game} dag {} {} av <@{}>, raderat av okänd",
+                    sheet.day,
+                    game.parser().describe_score(sheet.score),
+                    sheet.player_id
+                );
+                work.new_audit_entry(sheet.guild_id, None, "delete", Some(sheet.msg_id), &detail)
+                    .await?;
+                info!("Score sheet {} deleted: {detail}", sheet.msg_id);
+                days.insert((sheet.guild_id, game, sheet.day));
+                channels.insert(sheet.channel_id());
+            }
+            work.commit().await?;
+        }
+        // The retracted sheets are already excluded, so only remaining posts are touched
+        for (guild_id, game, day) in &days {
+            self.clear_medals(*guild_id, *game, *day, ctx).await?;
+            {
+                let database = self.database.write().await;
+                let mut work = database.begin().await?;
+                work.recompute_daily(*guild_id, *game, *day).await?;
+                work.commit().await?;
+            }
+            self.set_medals(*guild_id, *game, *day, ctx).await?;
+        }
+        let guilds: BTreeSet<i64> = days.iter().map(|(guild_id, _, _)| *guild_id).collect();
+        for guild_id in guilds {
+            self.database.write().await.update_cache(guild_id).await?;
+        }
+        Ok(channels)
+    }
+
+    // Scores the current cup under another rule from now on. Returns the current cup.
+    pub(crate) async fn set_scoring_rule(&self, guild_id: i64, scoring: Scoring) -> Result<Season> {
+        let mut database = self.database.write().await;
+        let season = database.current_season(guild_id).await?;
+        database
+            .set_scoring_rule(guild_id, season.id, scoring)
+            .await?;
+        database.update_cache(guild_id).await?;
+        Ok(season)
+    }
+
+    // Rebuilds the high scores of every day from the score sheets, and the ratings with them.
+    // Returns how many days there are.
+    pub(crate) async fn recompute_all(&self, guild_id: i64) -> Result<usize> {
+        let mut database = self.database.write().await;
+        let days = database.recompute_all_dailies(guild_id).await?;
+        database.recompute_ratings(guild_id).await?;
+        database.update_cache(guild_id).await?;
+        Ok(days)
+    }
+}
+
+// Results and settings always belong to the guild they were posted in
+fn guild_of(msg: &Message) -> Result<i64> {
+    msg.guild_id
+        .map(|guild_id| guild_id.0 as i64)
+        .context("Not posted in a guild")
 }
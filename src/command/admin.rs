@@ -0,0 +1,607 @@
+use anyhow::{anyhow, Context as _, Result};
+use log::{error, info};
+use serenity::{
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{
+                ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+            },
+            InteractionResponseType,
+        },
+        ChannelId, GuildId,
+    },
+    prelude::Context,
+};
+
+use super::{
+    config::subcommand,
+    score::{
+        bool_option, channel_option, game_option, integer_option, register_channel_option,
+        string_option,
+    },
+};
+use crate::{
+    announce_cup,
+    bot::Bot,
+    parser::Game,
+    scoring::{Ranking, Scoring, TieBreaker},
+};
+
+// Reports how far a long running admin command has come by editing its response
+pub(crate) struct Progress<'a> {
+    ctx: &'a Context,
+    command: &'a ApplicationCommandInteraction,
+}
+
+impl Progress<'_> {
+    pub(crate) async fn report(&self, content: impl ToString) -> Result<()> {
+        self.command
+            .edit_original_interaction_response(&self.ctx.http, |response| {
+                response.content(content)
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+// Runs an admin command for administrators and members with the admin role. Every use is
+// written to the audit log, with its outcome.
+pub(crate) async fn run(
+    bot: &Bot,
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Result<()> {
+    let (Some(guild_id), Some(member)) = (command.guild_id, command.member.as_ref()) else {
+        return respond(ctx, command, "Kommandona fungerar bara i en server.").await;
+    };
+    let guild = guild_id.0 as i64;
+    let actor = command.user.id.0 as i64;
+    let subcommand = command
+        .data
+        .options
+        .first()
+        .context("No admin subcommand")?;
+    let config = bot.database.read().await.get_config(guild).await?;
+    if !config.is_admin(member) {
+        bot.database
+            .write()
+            .await
+            .new_audit_entry(guild, Some(actor), "admin denied", None, &subcommand.name)
+            .await?;
+        return respond(ctx, command, "Du är ej betrodd med detta kommando").await;
+    }
+
+    // Acknowledged at once, the command may take longer than Discord waits for a response
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+        })
+        .await?;
+    let progress = Progress { ctx, command };
+    let options = subcommand.options.as_slice();
+    let result = match subcommand.name.as_str() {
+        "inläsning" => {
+            let channel_id = channel_option(options, "kanal")
+                .or(config.tracked_channel)
+                .map_or(command.channel_id, |channel_id| {
+                    ChannelId(channel_id as u64)
+                });
+            let from_start = bool_option(options, "allt");
+            backfill(bot, ctx, guild_id, channel_id, from_start, &progress).await
+        }
+        "omräkna" => recompute(bot, ctx, guild, options, &progress).await,
+        "kontroll" => check(bot, guild).await,
+        "ogiltig" => void(bot, ctx, guild, options).await,
+        "sammanfoga" => merge(bot, guild, options, &progress).await,
+        "annonsera" => announce(bot, ctx, guild, options, &progress).await,
+        "regel" => scoring_rule(bot, guild, options).await,
+        "rangordning" => cup_ranking(bot, guild, options).await,
+        "särskiljning" => tie_breakers(bot, guild, options).await,
+        "säsong" => season(bot, guild, options).await,
+        name => Err(anyhow!("Unknown admin command {name}")),
+    };
+    let (outcome, response) = match result {
+        Ok(response) => (response.clone(), response),
+        Err(e) => {
+            error!("{e}");
+            (
+                format!("failed: {e}"),
+                format!("Kommandot misslyckades: {e}"),
+            )
+        }
+    };
+    let action = format!("admin {}", subcommand.name);
+    bot.database
+        .write()
+        .await
+        .new_audit_entry(guild, Some(actor), &action, None, &outcome)
+        .await?;
+    info!("{action} in guild {guild}: {outcome}");
+    progress.report(response).await
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    content: &str,
+) -> Result<()> {
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.content(content))
+        })
+        .await?;
+    Ok(())
+}
+
+// Reads the results of a channel that were missed since it was last read, or all of them
+async fn backfill(
+    bot: &Bot,
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    from_start: bool,
+    progress: &Progress<'_>,
+) -> Result<String> {
+    progress
+        .report(format!("Läser in <#{}>…", channel_id.0))
+        .await?;
+    let backfill = bot
+        .backfill(guild_id, channel_id, from_start, ctx, Some(progress))
+        .await?;
+    Ok(format!("Läste in <#{}>: {backfill}.", channel_id.0))
+}
+
+// Recounts the placements of a day, or of every day, e.g. after editing the database by hand
+async fn recompute(
+    bot: &Bot,
+    ctx: &Context,
+    guild_id: i64,
+    options: &[CommandDataOption],
+    progress: &Progress<'_>,
+) -> Result<String> {
+    let game = game_option(options);
+    let Some(day) = integer_option(options, "dag") else {
+        progress.report("Räknar om alla dagar…").await?;
+        let days = bot.recompute_all(guild_id).await?;
+        return Ok(format!("Räknade om {days} dagar."));
+    };
+    progress
+        .report(format!("Räknar om {game} dag {day}…"))
+        .await?;
+    bot.recompute_day(guild_id, game, day, ctx).await?;
+    let high_scores = bot
+        .database
+        .read()
+        .await
+        .get_daily_high_scores(guild_id, game, day)
+        .await?;
+    let placements: Vec<String> = high_scores
+        .iter()
+        .map(|score| score.map_or("-".to_string(), |score| game.parser().describe_score(score)))
+        .collect();
+    Ok(format!(
+        "Räknade om {game} dag {day}: {}.",
+        placements.join("/")
+    ))
+}
+
+// Reports days whose stored high scores no longer match their score sheets
+async fn check(bot: &Bot, guild_id: i64) -> Result<String> {
+    let inconsistent = bot
+        .database
+        .read()
+        .await
+        .inconsistent_dailies(guild_id)
+        .await?;
+    let format = |scores: [Option<i64>; 3]| {
+        scores
+            .iter()
+            .map(|score| score.map_or("-".to_string(), |score| score.to_string()))
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    let mut response = format!("{} dagar med felaktiga placeringar.", inconsistent.len());
+    for (game, day, stored, derived) in inconsistent {
+        response.push_str(&format!(
+            "\n{game} dag {day}: sparat {}, beräknat {}",
+            format(stored),
+            format(derived)
+        ));
+    }
+    Ok(response)
+}
+
+// Stops counting a result, given its message id or link
+async fn void(
+    bot: &Bot,
+    ctx: &Context,
+    guild_id: i64,
+    options: &[CommandDataOption],
+) -> Result<String> {
+    let message = string_option(options, "meddelande").unwrap_or_default();
+    let Some(msg_id) = message
+        .trim()
+        .rsplit('/')
+        .next()
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return Ok(format!("{message} är inget meddelande-id."));
+    };
+    let Some(sheet) = bot.void_score(guild_id, msg_id as i64, ctx).await? else {
+        return Ok(format!("Meddelandet {msg_id} är inget räknat resultat."));
+    };
+    let game = sheet.game();
+    let reason = string_option(options, "anledning").unwrap_or("ingen anledning angiven");
+    Ok(format!(
+        "Resultatet {game} dag {} {} av <@{}> räknas inte längre ({reason}).",
+        sheet.day,
+        game.parser().describe_score(sheet.score),
+        sheet.player_id
+    ))
+}
+
+// Moves every result of one player to another, e.g. from an old account
+async fn merge(
+    bot: &Bot,
+    guild_id: i64,
+    options: &[CommandDataOption],
+    progress: &Progress<'_>,
+) -> Result<String> {
+    let (Some(from), Some(into)) = (user_option(options, "från"), user_option(options, "till"))
+    else {
+        return Ok(String::from("Ange två spelare."));
+    };
+    if from == into {
+        return Ok(String::from("Spelarna är samma."));
+    }
+    progress
+        .report(format!("Flyttar resultaten från <@{from}> till <@{into}>…"))
+        .await?;
+    let (moved, voided) = bot.merge_players(guild_id, from, into).await?;
+    Ok(format!(
+        "Flyttade {moved} resultat från <@{from}> till <@{into}>, {voided} dagar som båda spelat räknas inte längre."
+    ))
+}
+
+// Announces the winners of an ended cup right away, again if it already has been
+async fn announce(
+    bot: &Bot,
+    ctx: &Context,
+    guild_id: i64,
+    options: &[CommandDataOption],
+    progress: &Progress<'_>,
+) -> Result<String> {
+    let name = string_option(options, "cup").unwrap_or_default();
+    let (season, today) = {
+        let database = bot.database.read().await;
+        let season = database.get_season_by_name(guild_id, name).await?;
+        (season, database.get_config(guild_id).await?.today())
+    };
+    let Some(season) = season else {
+        return Ok(format!("Det finns ingen cup som heter {name}."));
+    };
+    if season.end >= today {
+        return Ok(format!("Cupen {} pågår till {}.", season.name, season.end));
+    }
+    let cup = match bool_option(options, "omnicup") {
+        true => "omni",
+        false => game_option(options).name(),
+    };
+    progress
+        .report(format!("Utser vinnaren av {cup}cupen {}…", season.name))
+        .await?;
+    bot.database
+        .write()
+        .await
+        .new_cup_result(guild_id, season.id, cup)
+        .await?;
+    match announce_cup(ctx, &bot.database, guild_id, &season, cup, true).await? {
+        true => Ok(format!("Vinnaren av {cup}cupen {} är utsedd.", season.name)),
+        false => Ok(format!(
+            "{cup}cupen {} har ingen vinnare att utse.",
+            season.name
+        )),
+    }
+}
+
+// Scores the current cup under another rule from now on
+async fn scoring_rule(bot: &Bot, guild_id: i64, options: &[CommandDataOption]) -> Result<String> {
+    let Some(scoring) = string_option(options, "regel").and_then(|s| s.parse::<Scoring>().ok())
+    else {
+        return Ok(String::from("Okänd regel."));
+    };
+    let season = bot.set_scoring_rule(guild_id, scoring).await?;
+    Ok(format!(
+        "Cupen {} räknas nu med regeln {scoring}.",
+        season.name
+    ))
+}
+
+// Chooses how the cup winners are ranked, by their sum or by their average
+async fn cup_ranking(bot: &Bot, guild_id: i64, options: &[CommandDataOption]) -> Result<String> {
+    let ranking = match string_option(options, "rangordning") {
+        Some("summa") => Ranking::Sum,
+        Some("snitt") => Ranking::Average {
+            min_games: integer_option(options, "minst").unwrap_or(0) as u32,
+            shrinkage: bool_option(options, "krympning"),
+        },
+        _ => return Ok(String::from("Okänd rangordning.")),
+    };
+    bot.database
+        .write()
+        .await
+        .set_cup_ranking(guild_id, ranking)
+        .await?;
+    Ok(format!("Cupvinnare rangordnas nu efter {ranking}."))
+}
+
+// Chooses how players tied for a cup win are separated, e.g. "guld snitt x först",
+// or "delad" to let them share the win
+async fn tie_breakers(bot: &Bot, guild_id: i64, options: &[CommandDataOption]) -> Result<String> {
+    let tie_breakers = match string_option(options, "ordning").unwrap_or_default().trim() {
+        "delad" => Ok(vec![]),
+        arguments => arguments
+            .split_whitespace()
+            .map(str::parse::<TieBreaker>)
+            .collect::<Result<Vec<TieBreaker>>>(),
+    };
+    let Ok(tie_breakers) = tie_breakers else {
+        let names: Vec<&str> = TieBreaker::ALL
+            .iter()
+            .map(TieBreaker::command_name)
+            .collect();
+        return Ok(format!(
+            "Ange delad eller några av {} i tur och ordning.",
+            names.join(", ")
+        ));
+    };
+    bot.database
+        .write()
+        .await
+        .set_tie_breakers(guild_id, &tie_breakers)
+        .await?;
+    match tie_breakers.is_empty() {
+        true => Ok(String::from("Vid delad förstaplats delas vinsten.")),
+        false => {
+            let names: Vec<String> = tie_breakers.iter().map(TieBreaker::to_string).collect();
+            Ok(format!(
+                "Vid delad förstaplats avgör {}.",
+                names.join(", sedan ")
+            ))
+        }
+    }
+}
+
+// Adds a custom season, e.g. a summer cup from 2024-06-01 to 2024-08-31
+async fn season(bot: &Bot, guild_id: i64, options: &[CommandDataOption]) -> Result<String> {
+    let date = |name| string_option(options, name).and_then(|date| date.trim().parse().ok());
+    let (Some(start), Some(end)) = (date("första"), date("sista")) else {
+        return Ok(String::from("Ange dagarna som t.ex. 2024-06-01."));
+    };
+    let name = match string_option(options, "namn").map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("{start} – {end}"),
+    };
+    let season = bot
+        .database
+        .write()
+        .await
+        .new_season(guild_id, &name, start, end)
+        .await;
+    match season {
+        Ok(season) => Ok(format!(
+            "Cupen {} pågår från {start} till {end}.",
+            season.name
+        )),
+        Err(e) => Ok(format!("Kunde inte lägga till säsongen: {e}.")),
+    }
+}
+
+fn user_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .and_then(|value| match value {
+            CommandDataOptionValue::User(user, _) => Some(user.id.0 as i64),
+            _ => None,
+        })
+}
+
+// The game of the subcommand, Wordle unless the option is given
+fn register_game_sub_option(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .name("spel")
+        .description("spelet, Wordle om inget anges.")
+        .kind(CommandOptionType::String)
+        .required(false);
+    for game in Game::ALL {
+        option.add_string_choice(game, game.name());
+    }
+    option
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("admin")
+        .description("Underhåll av resultaten, bara för administratörer.")
+        .create_option(|option| {
+            subcommand(
+                option,
+                "inläsning",
+                "läs in missade resultat ur en kanal, resultatkanalen om ingen anges.",
+            )
+            .create_sub_option(|option| register_channel_option(option))
+            .create_sub_option(|option| {
+                option
+                    .name("allt")
+                    .description("läs hela kanalen, inte bara det som skrivits sedan sist.")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "omräkna",
+                "räkna om placeringarna för en dag, alla dagar om ingen anges.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("dag")
+                    .description("dagens nummer.")
+                    .kind(CommandOptionType::Integer)
+                    .required(false)
+            })
+            .create_sub_option(|option| register_game_sub_option(option))
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "kontroll",
+                "visa dagar vars placeringar inte stämmer med resultaten.",
+            )
+        })
+        .create_option(|option| {
+            subcommand(option, "ogiltig", "sluta räkna ett resultat.")
+                .create_sub_option(|option| {
+                    option
+                        .name("meddelande")
+                        .description("resultatets meddelande-id eller länk.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("anledning")
+                        .description("varför resultatet inte räknas.")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "sammanfoga",
+                "flytta alla resultat från en spelare till en annan.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("från")
+                    .description("spelaren vars resultat flyttas.")
+                    .kind(CommandOptionType::User)
+                    .required(true)
+            })
+            .create_sub_option(|option| {
+                option
+                    .name("till")
+                    .description("spelaren som får resultaten.")
+                    .kind(CommandOptionType::User)
+                    .required(true)
+            })
+        })
+        .create_option(|option| {
+            subcommand(option, "annonsera", "utse vinnaren av en avslutad cup nu.")
+                .create_sub_option(|option| {
+                    option
+                        .name("cup")
+                        .description("cupen, t.ex. 2023-01.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| register_game_sub_option(option))
+                .create_sub_option(|option| {
+                    option
+                        .name("omnicup")
+                        .description("utse vinnaren av omnicupen.")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            subcommand(option, "regel", "poängregeln för den pågående cupen.").create_sub_option(
+                |option| {
+                    option
+                        .name("regel")
+                        .description("poängregeln.")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+                    for scoring in Scoring::ALL {
+                        option.add_string_choice(scoring, scoring.name());
+                    }
+                    option
+                },
+            )
+        })
+        .create_option(|option| {
+            subcommand(option, "rangordning", "hur cupvinnare rangordnas.")
+                .create_sub_option(|option| {
+                    option
+                        .name("rangordning")
+                        .description("summan eller snittet av poängen.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .add_string_choice("summa", "summa")
+                        .add_string_choice("snitt", "snitt")
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("minst")
+                        .description("antal spel som krävs för att rangordnas efter snitt.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("krympning")
+                        .description("dra snittet av den som spelat lite mot allas snitt.")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "särskiljning",
+                "hur delad förstaplats i en cup avgörs.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("ordning")
+                    .description("t.ex. guld snitt x först, eller delad för att dela vinsten.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+        })
+        .create_option(|option| {
+            subcommand(option, "säsong", "lägg till en egen säsong framåt i tiden.")
+                .create_sub_option(|option| {
+                    option
+                        .name("första")
+                        .description("säsongens första dag, t.ex. 2024-06-01.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("sista")
+                        .description("säsongens sista dag, t.ex. 2024-08-31.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("namn")
+                        .description("säsongens namn, första och sista dagen om inget anges.")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+}
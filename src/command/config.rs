@@ -0,0 +1,367 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono_tz::Tz;
+use log::info;
+use serenity::{
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{CommandDataOption, CommandDataOptionValue},
+        GuildId, Member,
+    },
+    prelude::{Context, RwLock},
+    utils::MessageBuilder,
+};
+
+use super::score::{
+    bool_option, channel_option, integer_option, register_channel_option, string_option,
+};
+use crate::{
+    config::{GuildConfig, Language},
+    database::CachedDatabase as Database,
+    scoring::Scoring,
+    season::Period,
+};
+
+// Shows or changes the settings of the guild, anyone may see them but only administrators
+// may change them
+pub(crate) async fn run(
+    database: &Arc<RwLock<Database>>,
+    _ctx: &Context,
+    guild_id: GuildId,
+    member: Option<&Member>,
+    options: &[CommandDataOption],
+) -> Result<String> {
+    let guild = guild_id.0 as i64;
+    let mut config = database.read().await.get_config(guild).await?;
+    let Some(subcommand) = options.first().filter(|option| option.name != "visa") else {
+        return Ok(show(&config));
+    };
+    let Some(member) = member.filter(|member| config.is_admin(member)) else {
+        return Ok(String::from("Du är ej betrodd med detta kommando"));
+    };
+    let options = subcommand.options.as_slice();
+    let change = match subcommand.name.as_str() {
+        "kanal" => {
+            config.tracked_channel = channel_option(options, "kanal");
+            match config.tracked_channel {
+                Some(channel) => format!("Resultat räknas nu bara i <#{channel}>."),
+                None => String::from("Resultat räknas nu i alla kanaler."),
+            }
+        }
+        "annonskanal" => {
+            config.announcement_channel = channel_option(options, "kanal");
+            match config.announcement_channel {
+                Some(channel) => format!("Cupvinnare utses nu i <#{channel}>."),
+                None => String::from("Cupvinnare utses nu i resultatkanalen."),
+            }
+        }
+        "regel" => {
+            let Some(scoring) = string_option(options, "regel").and_then(|s| s.parse().ok()) else {
+                return Ok(String::from("Okänd regel."));
+            };
+            config.scoring = scoring;
+            format!("Cuper utan egen regel räknas nu med regeln {scoring}.")
+        }
+        "säsong" => {
+            let Some(period) = string_option(options, "längd").and_then(|s| s.parse().ok()) else {
+                return Ok(String::from("Okänd säsongslängd."));
+            };
+            config.period = period;
+            format!("Nya säsonger räknas nu per {period}.")
+        }
+        "tidszon" => {
+            let name = string_option(options, "tidszon").unwrap_or_default();
+            let Ok(time_zone) = name.trim().parse::<Tz>() else {
+                return Ok(format!(
+                    "Okänd tidszon {name}, använd t.ex. Europe/Stockholm."
+                ));
+            };
+            config.time_zone = time_zone;
+            format!("Dagar och cuper räknas nu i tidszonen {time_zone}.")
+        }
+        "språk" => {
+            let Some(language) = string_option(options, "språk").and_then(|s| s.parse().ok())
+            else {
+                return Ok(String::from("Okänt språk."));
+            };
+            config.language = language;
+            format!("Cupvinnare och ledare visas nu på {language}.")
+        }
+        "reaktioner" => {
+            config.reactions = bool_option(options, "på");
+            match config.reactions {
+                true => String::from("Resultat får nu medaljer som reaktioner."),
+                false => String::from("Resultat får inte längre några reaktioner."),
+            }
+        }
+        "straff" => {
+            config.missed_day_penalty = integer_option(options, "poäng").map(|p| p as u32);
+            match config.missed_day_penalty {
+                Some(penalty) => format!("Varje missad dag kostar nu {penalty}p i cuperna."),
+                None => String::from("Missade dagar räknas inte längre i cuperna."),
+            }
+        }
+        "rutnät" => {
+            config.grid_validation = bool_option(options, "på");
+            match config.grid_validation {
+                true => String::from("Resultat räknas nu bara om rutnätet stämmer."),
+                false => String::from("Resultat räknas nu utan att rutnätet kontrolleras."),
+            }
+        }
+        "svårt" => {
+            config.hard_mode_bonus = integer_option(options, "poäng").unwrap_or(0) as u32;
+            format!(
+                "Lösta spel i svårt läge ger nu {}p extra.",
+                config.hard_mode_bonus
+            )
+        }
+        "adminroll" => {
+            config.admin_role = role_option(options, "roll");
+            match config.admin_role {
+                Some(role) => {
+                    format!("Medlemmar med rollen <@&{role}> får nu ändra inställningarna.")
+                }
+                None => String::from("Bara administratörer får nu ändra inställningarna."),
+            }
+        }
+        _ => return Ok(show(&config)),
+    };
+    {
+        let mut database = database.write().await;
+        // A change is never made without its audit entry
+        let mut work = database.begin().await?;
+        work.set_config(guild, &config).await?;
+        work.new_audit_entry(
+            guild,
+            Some(member.user.id.0 as i64),
+            "config",
+            None,
+            &change,
+        )
+        .await?;
+        work.commit().await?;
+        // The current cup may be scored under another rule, or be another season altogether
+        database.update_cache(guild).await?;
+    }
+    info!("Config of guild {guild} changed: {change}");
+    Ok(change)
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("config")
+        .description("Serverns inställningar, bara administratörer får ändra dem.")
+        .create_option(|option| {
+            option
+                .name("visa")
+                .description("visa alla inställningar.")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "kanal",
+                "kanalen där resultat räknas, alla om ingen anges.",
+            )
+            .create_sub_option(|option| register_channel_option(option))
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "annonskanal",
+                "kanalen där cupvinnare utses, resultatkanalen om ingen anges.",
+            )
+            .create_sub_option(|option| register_channel_option(option))
+        })
+        .create_option(|option| {
+            subcommand(option, "regel", "poängregeln för cuper utan egen regel.").create_sub_option(
+                |option| {
+                    option
+                        .name("regel")
+                        .description("poängregeln.")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+                    for scoring in Scoring::ALL {
+                        option.add_string_choice(scoring, scoring.name());
+                    }
+                    option
+                },
+            )
+        })
+        .create_option(|option| {
+            subcommand(option, "säsong", "längden på nya säsonger.").create_sub_option(|option| {
+                option
+                    .name("längd")
+                    .description("säsongens längd.")
+                    .kind(CommandOptionType::String)
+                    .required(true);
+                for period in Period::ALL {
+                    option.add_string_choice(period, period.value());
+                }
+                option
+            })
+        })
+        .create_option(|option| {
+            subcommand(option, "tidszon", "tidszonen som dagar och cuper räknas i.")
+                .create_sub_option(|option| {
+                    option
+                        .name("tidszon")
+                        .description("t.ex. Europe/Stockholm.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            subcommand(option, "språk", "språket cupvinnare och ledare visas på.")
+                .create_sub_option(|option| {
+                    option
+                        .name("språk")
+                        .description("språket.")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+                    for language in Language::ALL {
+                        option.add_string_choice(language, language.value());
+                    }
+                    option
+                })
+        })
+        .create_option(|option| {
+            subcommand(option, "reaktioner", "medaljer som reaktioner på resultat.")
+                .create_sub_option(|option| {
+                    option
+                        .name("på")
+                        .description("om resultat får reaktioner.")
+                        .kind(CommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "straff",
+                "poängen en missad dag kostar i cuperna, missade dagar räknas inte om inget anges.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("poäng")
+                    .description("poängavdraget per missad dag.")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(0)
+                    .required(false)
+            })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "rutnät",
+                "om rutnätet i ett resultat måste stämma med poängen.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("på")
+                    .description("om rutnätet kontrolleras.")
+                    .kind(CommandOptionType::Boolean)
+                    .required(true)
+            })
+        })
+        .create_option(|option| {
+            subcommand(option, "svårt", "extrapoängen för lösta spel i svårt läge.")
+                .create_sub_option(|option| {
+                    option
+                        .name("poäng")
+                        .description("bonusen per löst spel i svårt läge.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            subcommand(
+                option,
+                "adminroll",
+                "rollen som får ändra inställningarna, bara administratörer om ingen anges.",
+            )
+            .create_sub_option(|option| {
+                option
+                    .name("roll")
+                    .description("adminrollen.")
+                    .kind(CommandOptionType::Role)
+                    .required(false)
+            })
+        })
+}
+
+pub(crate) fn subcommand<'a>(
+    option: &'a mut CreateApplicationCommandOption,
+    name: &str,
+    description: &str,
+) -> &'a mut CreateApplicationCommandOption {
+    option
+        .name(name)
+        .description(description)
+        .kind(CommandOptionType::SubCommand)
+}
+
+fn role_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .and_then(|value| match value {
+            CommandDataOptionValue::Role(role) => Some(role.id.0 as i64),
+            _ => None,
+        })
+}
+
+fn show(config: &GuildConfig) -> String {
+    let channel = |channel: Option<i64>, default: &str| {
+        channel.map_or(default.to_string(), |channel| format!("<#{channel}>"))
+    };
+    let mut response = MessageBuilder::new();
+    response.push_bold_line("Inställningar:");
+    response.push_line(format!(
+        "\t\tResultatkanal: {}",
+        channel(config.tracked_channel, "alla kanaler")
+    ));
+    response.push_line(format!(
+        "\t\tAnnonskanal: {}",
+        channel(config.announcement_channel, "resultatkanalen")
+    ));
+    response.push_line(format!("\t\tRegel: {}", config.scoring));
+    response.push_line(format!("\t\tSäsongslängd: {}", config.period));
+    response.push_line(format!("\t\tTidszon: {}", config.time_zone));
+    response.push_line(format!("\t\tSpråk: {}", config.language));
+    response.push_line(format!(
+        "\t\tReaktioner: {}",
+        match config.reactions {
+            true => "på",
+            false => "av",
+        }
+    ));
+    response.push_line(format!(
+        "\t\tStraff för missad dag: {}",
+        config
+            .missed_day_penalty
+            .map_or(String::from("inget"), |penalty| format!("{penalty}p"))
+    ));
+    response.push_line(format!(
+        "\t\tRutnät kontrolleras: {}",
+        match config.grid_validation {
+            true => "ja",
+            false => "nej",
+        }
+    ));
+    response.push_line(format!(
+        "\t\tBonus för svårt läge: {}p",
+        config.hard_mode_bonus
+    ));
+    response.push_line(format!(
+        "\t\tAdminroll: {}",
+        config
+            .admin_role
+            .map_or(String::from("ingen"), |role| format!("<@&{role}>"))
+    ));
+    response.build()
+}
@@ -3,39 +3,65 @@ use std::sync::Arc;
 use anyhow::Result;
 use serenity::{
     builder::CreateApplicationCommand,
-    model::prelude::interaction::application_command::CommandDataOption, prelude::{Context, RwLock},
+    model::prelude::{interaction::application_command::CommandDataOption, GuildId},
+    prelude::{Context, RwLock},
     utils::MessageBuilder,
 };
 
-use super::score::FIB;
-use crate::{database::CachedDatabase as Database, scoresheet::Scoresheet, Placement, GUILD_ID};
+use super::score::{game_option, register_game_option};
+use crate::{
+    database::CachedDatabase as Database, parser::Game, scoresheet::Scoresheet, Placement,
+};
 
 pub(crate) async fn run(
     database: &Arc<RwLock<Database>>,
     ctx: &Context,
-    _options: &[CommandDataOption],
+    guild_id: GuildId,
+    options: &[CommandDataOption],
 ) -> Result<String> {
-    let daily = fetch_daily_result(database).await?;
+    let guild = guild_id.0 as i64;
+    let game = game_option(options);
+    let daily = fetch_daily_result(database, guild, game).await?;
+    let (rule, config) = {
+        let database = database.read().await;
+        let season = database.current_season(guild).await?;
+        (
+            database.get_scoring_rule(guild, season.id).await?.rule(),
+            database.get_config(guild).await?,
+        )
+    };
     let mut response = MessageBuilder::new();
-    response.push_bold_line("Dagens placering:");
+    response.push_bold_line(format!("Dagens placering ({game}):"));
     for (placement, score_sheets) in daily {
         let Some(score_sheets) = score_sheets else {break;};
         let score = score_sheets[0].score();
+        let field = database
+            .read()
+            .await
+            .get_day_scores(guild, game, score_sheets[0].day)
+            .await?;
+        let points = rule.points(game, score, 0, &field);
         let mut users: Vec<String> = Vec::default();
         for sheet in score_sheets {
-            let nick = sheet
-                .player()
-                .get_nick(GUILD_ID.into(), &ctx.http)
-                .await
-                .unwrap();
-            users.push(nick);
+            let nick = sheet.player().get_nick(guild_id, &ctx.http).await.unwrap();
+            // Hard mode is marked the same way as in the share message, with its bonus if any
+            match sheet.hard_mode() {
+                true => {
+                    let hard_mode_points = rule.points(game, score, config.bonus(true), &field);
+                    match hard_mode_points == points {
+                        true => users.push(format!("{nick}\\*")),
+                        false => users.push(format!("{nick}\\* ({hard_mode_points}p)")),
+                    }
+                }
+                false => users.push(nick),
+            }
         }
         let line = format!(
-            "{} - {} - {} försök ({}p)",
+            "{} - {} - {} ({}p)",
             placement,
             users.join(", "),
-            score,
-            FIB[score as usize]
+            game.parser().describe_score(score),
+            points
         );
         response.push_line(line);
     }
@@ -43,16 +69,29 @@ pub(crate) async fn run(
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("dagens").description("Dagens gissningar.")
+    register_game_option(command)
+        .name("dagens")
+        .description("Dagens gissningar.")
 }
 
 async fn fetch_daily_result(
     database: &Arc<RwLock<Database>>,
+    guild_id: i64,
+    game: Game,
 ) -> Result<[(Placement, Option<Vec<Scoresheet>>); 3]> {
     let database = database.read().await;
     Ok([
-        (Placement::Gold, database.get_gold_medalist(None).await?),
-        (Placement::Silver, database.get_silver_medalist(None).await?),
-        (Placement::Bronze, database.get_bronze_medalist(None).await?),
+        (
+            Placement::Gold,
+            database.get_gold_medalist(guild_id, game, None).await?,
+        ),
+        (
+            Placement::Silver,
+            database.get_silver_medalist(guild_id, game, None).await?,
+        ),
+        (
+            Placement::Bronze,
+            database.get_bronze_medalist(guild_id, game, None).await?,
+        ),
     ])
 }
@@ -0,0 +1,146 @@
+use std::{cmp::Reverse, collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType, interaction::application_command::CommandDataOption, GuildId,
+    },
+    prelude::{Context, RwLock},
+    utils::MessageBuilder,
+};
+
+use super::score::{bool_option, game_option, register_game_option, string_option};
+use crate::{
+    database::{CachedDatabase as Database, CupResult},
+    player::Player,
+    season::Season,
+    Placement,
+};
+
+// Past champions, most titles and podium counts of the closed cups of a game, or the final
+// standings of one of them
+pub(crate) async fn run(
+    database: &Arc<RwLock<Database>>,
+    ctx: &Context,
+    guild_id: GuildId,
+    options: &[CommandDataOption],
+) -> Result<String> {
+    let game = game_option(options);
+    let (cup, cup_name) = match bool_option(options, "omnicup") {
+        true => ("omni", String::from("omnicupen")),
+        false => (game.name(), format!("cupen ({game})")),
+    };
+    let history = database
+        .read()
+        .await
+        .get_cup_history(guild_id.0 as i64, cup)
+        .await?;
+    let mut response = MessageBuilder::new();
+    if let Some(season) = string_option(options, "cup") {
+        let season = Season::normalise_name(season);
+        let standings: Vec<&CupResult> = history
+            .iter()
+            .filter(|result| result.season == season)
+            .collect();
+        if standings.is_empty() {
+            return Ok(format!("Det finns ingen avslutad cup som heter {season}."));
+        }
+        response.push_bold_line(format!("Slutställning i {cup_name} {season}:"));
+        for result in standings {
+            let user = nick(ctx, guild_id, result.player).await?;
+            let [gold, silver, bronze] = result.medals;
+            let placement = match Placement::from_rank(result.placement) {
+                Placement::Loser => format!("{}.", result.placement),
+                medal => medal.to_string(),
+            };
+            response.push_line(format!(
+                "\t\t{placement} {user}: {}p, {} {gold} {} {silver} {} {bronze}",
+                result.points,
+                Placement::Gold,
+                Placement::Silver,
+                Placement::Bronze
+            ));
+        }
+        return Ok(response.build());
+    }
+    if history.is_empty() {
+        return Ok(format!("Ingen {cup_name} har avslutats än."));
+    }
+
+    response.push_bold_line(format!("Mästare i {cup_name}:"));
+    let mut champions: Vec<(&str, Vec<String>)> = vec![];
+    for result in history.iter().filter(|result| result.placement == 1) {
+        let user = nick(ctx, guild_id, result.player).await?;
+        // Shared wins list every winner of the season together
+        match champions.last_mut() {
+            Some((season, users)) if *season == result.season => users.push(user),
+            _ => champions.push((&result.season, vec![user])),
+        }
+    }
+    for (season, users) in champions {
+        response.push_line(format!("\t\t{season}: {}", users.join(", ")));
+    }
+
+    let podiums = podiums(&history);
+    response.push_bold_line("Flest titlar:");
+    let mut titles: Vec<&(Player, [u32; 3])> = podiums
+        .iter()
+        .filter(|(_, [gold, _, _])| *gold > 0)
+        .collect();
+    titles.sort_by_key(|(_, [gold, _, _])| Reverse(*gold));
+    for (player, [gold, _, _]) in titles {
+        response.push_line(format!(
+            "\t\t{gold}: {}",
+            nick(ctx, guild_id, *player).await?
+        ));
+    }
+    response.push_bold_line("Pallplatser:");
+    for (player, [gold, silver, bronze]) in &podiums {
+        response.push_line(format!(
+            "\t\t{} {gold} {} {silver} {} {bronze}: {}",
+            Placement::Gold,
+            Placement::Silver,
+            Placement::Bronze,
+            nick(ctx, guild_id, *player).await?
+        ));
+    }
+    Ok(response.build())
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    register_game_option(command)
+        .name("hall")
+        .description("Tidigare cupmästare, flest titlar och pallplatser.")
+        .create_option(|option| {
+            option
+                .name("omnicup")
+                .description("visa omnicupen för alla spel.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("cup")
+                .description("slutställningen i en avslutad cup, t.ex. 2023-01.")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+}
+
+async fn nick(ctx: &Context, guild_id: GuildId, player: Player) -> Result<String> {
+    player.get_nick(guild_id, &ctx.http).await
+}
+
+// The first, second and third places of every player on the podium, most golds first
+fn podiums(history: &[CupResult]) -> Vec<(Player, [u32; 3])> {
+    let mut podiums: HashMap<Player, [u32; 3]> = HashMap::new();
+    for result in history {
+        if let 1..=3 = result.placement {
+            podiums.entry(result.player).or_default()[result.placement as usize - 1] += 1;
+        }
+    }
+    let mut podiums: Vec<(Player, [u32; 3])> = podiums.into_iter().collect();
+    podiums.sort_by_key(|(player, medals)| (Reverse(*medals), player.id));
+    podiums
+}
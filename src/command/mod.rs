@@ -1,3 +1,7 @@
 pub mod stats;
 pub mod score;
 pub mod daily;
+pub mod rating;
+pub mod hall;
+pub mod config;
+pub mod admin;
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{interaction::application_command::CommandDataOption, GuildId},
+    prelude::{Context, RwLock},
+    utils::MessageBuilder,
+};
+
+use crate::database::CachedDatabase as Database;
+
+// Glicko-2 ratings over all games, shown with a 95% confidence interval
+pub(crate) async fn run(
+    database: &Arc<RwLock<Database>>,
+    ctx: &Context,
+    guild_id: GuildId,
+    _options: &[CommandDataOption],
+) -> Result<String> {
+    let ratings = database.read().await.get_ratings(guild_id.0 as i64).await?;
+    let mut response = MessageBuilder::new();
+    // The ratings are replayed at midnight, not for every result
+    response.push_bold_line("Rating över alla spel, uppdaterad vid midnatt:");
+    for (player, rating) in ratings {
+        let user = player.get_nick(guild_id, &ctx.http).await?;
+        response.push_line(format!(
+            "\t\t{:.0} ±{:.0}: {user}",
+            rating.rating,
+            2.0 * rating.deviation
+        ));
+    }
+    Ok(response.build())
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("rating")
+        .description("Skicklighet över alla spel, där varje dag är en match.")
+}
@@ -3,54 +3,216 @@ use std::sync::Arc;
 use anyhow::Result;
 use log::debug;
 use serenity::{
-    builder::CreateApplicationCommand,
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
     model::prelude::{
         command::CommandOptionType,
         interaction::application_command::{CommandDataOption, CommandDataOptionValue},
+        ChannelType, GuildId,
     },
     prelude::*,
     utils::MessageBuilder,
 };
 
 use crate::{
-    database::CachedDatabase as Database, utils::current_cup_number_cute_format, GUILD_ID,
+    database::CachedDatabase as Database,
+    parser::Game,
+    scoring::{Ranking, Scoring},
 };
 
 // Failure (X) gives a score of zero
 pub const FIB: [u32; 7] = [0, 13, 8, 5, 3, 2, 1];
 
+// Scores past the end of the scoring table get the points of the last entry. Only solved
+// games earn the bonus.
+pub fn points(game: Game, score: i64, bonus: u32) -> u32 {
+    let table = game.parser().scoring_table();
+    let bonus = match score {
+        0 => 0,
+        _ => bonus,
+    };
+    table
+        .get(score as usize)
+        .or(table.last())
+        .copied()
+        .unwrap_or(0)
+        + bonus
+}
+
+// What the best result of a day is worth in the omnicup, whatever the game
+pub const OMNI_POINTS: u32 = 100;
+
+// Scales points by the best result of the day, so that every game weighs the same
+pub fn omni_points(points: u32, best: u32) -> u32 {
+    match best {
+        0 => 0,
+        best => OMNI_POINTS * points / best,
+    }
+}
+
+pub(crate) fn bool_option(options: &[CommandDataOption], name: &str) -> bool {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .map(|value| matches!(value, CommandDataOptionValue::Boolean(true)))
+        .unwrap_or(false)
+}
+
+pub(crate) fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .and_then(|value| match value {
+            CommandDataOptionValue::String(value) => Some(value.as_str()),
+            _ => None,
+        })
+}
+
+pub(crate) fn channel_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .and_then(|value| match value {
+            CommandDataOptionValue::Channel(channel) => Some(channel.id.0 as i64),
+            _ => None,
+        })
+}
+
+pub(crate) fn integer_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| opt.resolved.as_ref())
+        .and_then(|value| match value {
+            CommandDataOptionValue::Integer(value) => Some(*value),
+            _ => None,
+        })
+}
+
+// The game to show, Wordle unless the option is given
+pub(crate) fn game_option(options: &[CommandDataOption]) -> Game {
+    string_option(options, "spel")
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Game::Wordle)
+}
+
+pub(crate) fn register_game_option(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command.create_option(|option| {
+        option
+            .name("spel")
+            .description("spelet att visa, Wordle om inget anges.")
+            .kind(CommandOptionType::String)
+            .required(false);
+        for game in Game::ALL {
+            option.add_string_choice(game, game.name());
+        }
+        option
+    })
+}
+
+// An optional text channel named "kanal"
+pub(crate) fn register_channel_option(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .name("kanal")
+        .description("kanalen.")
+        .kind(CommandOptionType::Channel)
+        .channel_types(&[ChannelType::Text])
+        .required(false)
+}
+
 pub(crate) async fn run(
     database: &Arc<RwLock<Database>>,
     ctx: &Context,
+    guild_id: GuildId,
     options: &[CommandDataOption],
 ) -> Result<String> {
     debug!("{:?}", options);
-    // XXX
-    let totala: bool = {
-        if let Some(opt) = options.get(0) {
-            if let Some(CommandDataOptionValue::Boolean(total)) = opt.resolved.as_ref() {
-                *total
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+    let guild = guild_id.0 as i64;
+    let totala = bool_option(options, "totala");
+    let hard_mode = bool_option(options, "svårt");
+    let omnicup = bool_option(options, "omnicup");
+    let game = game_option(options);
+    let scoring: Option<Scoring> = string_option(options, "regel").and_then(|s| s.parse().ok());
+    let cup = string_option(options, "cup");
+    let ranking = match bool_option(options, "snitt") {
+        true => Ranking::Average {
+            min_games: integer_option(options, "minst").unwrap_or(0).max(0) as u32,
+            shrinkage: bool_option(options, "krympning"),
+        },
+        false => Ranking::Sum,
     };
-    debug!("Totala: {totala}");
-    let mut response = MessageBuilder::new();
+    debug!(
+        "Spel: {game}, Totala: {totala}, Svårt: {hard_mode}, Omnicup: {omnicup}, Regel: {scoring:?}, Cup: {cup:?}, Rangordning: {ranking}"
+    );
+    // Only the current cups under their own rules are cached, anything else is computed on request
+    let recompute = scoring.is_some() || cup.is_some() || ranking != Ranking::Sum;
     let database = database.read().await;
-    let score = if totala {
-        response.push_bold_line("Ställning i totalcupen:");
-        database.total_cup_score()
+    let season = match cup {
+        Some(cup) => match database.get_season_by_name(guild, cup).await? {
+            Some(season) => season,
+            None => return Ok(format!("Det finns ingen cup som heter {cup}.")),
+        },
+        None => database.current_season(guild).await?,
+    };
+    let cup_name = &season.name;
+    let mut rule_name = scoring.map_or(String::new(), |scoring| format!(", regel {scoring}"));
+    if ranking != Ranking::Sum {
+        rule_name.push_str(&format!(", {ranking}"));
+    }
+    let mut response = MessageBuilder::new();
+    let score = if omnicup {
+        response.push_bold_line(format!("Ställning i omnicupen {cup_name}{rule_name}:"));
+        match recompute {
+            true => {
+                database
+                    .omnicup_score(guild, season.id, scoring, ranking)
+                    .await?
+            }
+            false => database.current_omnicup_score(guild).to_vec(),
+        }
+    } else if totala {
+        response.push_bold_line(format!("Ställning i totalcupen ({game}{rule_name}):"));
+        match recompute {
+            true => database.total(guild, game, scoring, ranking).await?,
+            false => database.total_cup_score(guild, game).to_vec(),
+        }
+    } else if hard_mode {
+        response.push_bold_line(format!(
+            "Ställning i svåra cupen {cup_name} ({game}{rule_name}):"
+        ));
+        match recompute {
+            true => {
+                database
+                    .cup_score(guild, game, season.id, true, scoring, ranking)
+                    .await?
+            }
+            false => database.current_hard_mode_cup_score(guild, game).to_vec(),
+        }
     } else {
-        let cup_number = current_cup_number_cute_format();
-        response.push_bold_line(format!("Ställning i månadscupen {cup_number}:"));
-        database.current_cup_score()
+        response.push_bold_line(format!("Ställning i cupen {cup_name} ({game}{rule_name}):"));
+        match recompute {
+            true => {
+                database
+                    .cup_score(guild, game, season.id, false, scoring, ranking)
+                    .await?
+            }
+            false => database.current_cup_score(guild, game).to_vec(),
+        }
     };
     debug!("Writing results.");
     for (player, result) in score {
-        let user = player.get_nick(GUILD_ID.into(), &ctx.http).await?;
+        let user = player.get_nick(guild_id, &ctx.http).await?;
+        // Averages are kept in hundredths
+        let result = match ranking {
+            Ranking::Sum => result.to_string(),
+            Ranking::Average { .. } => format!("{:.2}", result as f64 / 100.0),
+        };
         response.push_line(format!("\t\t{result}: {user}"));
     }
     let response = response.build();
@@ -59,7 +221,7 @@ pub(crate) async fn run(
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
+    register_game_option(command)
         .name("ställning")
         .description("Nuvarande ställning i månadscupen.")
         .create_option(|option| {
@@ -69,4 +231,58 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .kind(CommandOptionType::Boolean)
                 .required(false)
         })
+        .create_option(|option| {
+            option
+                .name("svårt")
+                .description("ställning med bara spel i svårt läge.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("omnicup")
+                .description("ställning i månadscupen för alla spel.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("regel")
+                .description("räkna om ställningen med en annan poängregel.")
+                .kind(CommandOptionType::String)
+                .required(false);
+            for scoring in Scoring::ALL {
+                option.add_string_choice(scoring, scoring.name());
+            }
+            option
+        })
+        .create_option(|option| {
+            option
+                .name("snitt")
+                .description("rangordna efter snittpoäng per spel i stället för summan.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("minst")
+                .description("minsta antal spel för att räknas i snittet.")
+                .kind(CommandOptionType::Integer)
+                .min_int_value(0)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("krympning")
+                .description("dra snittet mot hela fältets snitt för spelare med få spel.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("cup")
+                .description("en tidigare cup, t.ex. 2023-01.")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
 }
@@ -3,26 +3,47 @@ use std::{collections::HashMap, sync::Arc};
 use anyhow::Result;
 use serenity::{
     builder::CreateApplicationCommand,
-    model::prelude::interaction::application_command::CommandDataOption, utils::MessageBuilder, prelude::RwLock,
+    model::prelude::{
+        command::CommandOptionType, interaction::application_command::CommandDataOption, GuildId,
+    },
+    prelude::RwLock,
+    utils::MessageBuilder,
 };
 
+use super::score::{bool_option, game_option, register_game_option};
 use crate::{database::CachedDatabase as Database, player::Player, Placement};
 
 // Collects, calculates and presents various statistics
-// for a given player.
+// for a given player in the guild.
 pub(crate) async fn run(
     player: &Player,
+    guild_id: GuildId,
     database: &Arc<RwLock<Database>>,
-    _options: &[CommandDataOption],
+    options: &[CommandDataOption],
 ) -> Result<String> {
+    let guild = guild_id.0 as i64;
+    let game = game_option(options);
     let database = database.read().await;
     let mut response = MessageBuilder::new();
-    let gold_medals = database.get_user_gold_medals(player.id as i64).await?;
-    let silver_medals = database.get_user_silver_medals(player.id as i64).await?;
-    let bronze_medals = database.get_user_bronze_medals(player.id as i64).await?;
-    let played_games = database.get_user_played_games(player.id as i64).await?;
+    let gold_medals = database
+        .get_user_gold_medals(guild, player.id as i64, game)
+        .await?;
+    let silver_medals = database
+        .get_user_silver_medals(guild, player.id as i64, game)
+        .await?;
+    let bronze_medals = database
+        .get_user_bronze_medals(guild, player.id as i64, game)
+        .await?;
+    let played_games = database
+        .get_user_played_games(guild, player.id as i64, game)
+        .await?;
+    let hard_mode_games = database
+        .get_user_hard_mode_games(guild, player.id as i64, game)
+        .await?;
 
+    response.push_bold_line(format!("{game}:"));
     response.push_line(format!("Antal spelade spel: **{}**", played_games));
+    response.push_line(format!("Varav i svårt läge\\*: **{}**", hard_mode_games));
     for (p, m) in [
         (Placement::Gold.to_string(), gold_medals),
         (Placement::Silver.to_string(), silver_medals),
@@ -30,24 +51,34 @@ pub(crate) async fn run(
     ] {
         response.push_line(format!("{p} medaljer: **{m}**"));
     }
+    if bool_option(options, "rating") {
+        if let Some(rating) = database.get_player_rating(guild, player.id as i64).await? {
+            response.push_line(format!(
+                "Rating: **{:.0}** ±{:.0}",
+                rating.rating,
+                2.0 * rating.deviation
+            ));
+        }
+    }
     response.push_line("");
 
-    let scores = database.get_user_scores(player.id as i64).await;
+    let scores = database
+        .get_user_scores(guild, player.id as i64, game)
+        .await;
     let total: f64 = scores.len() as f64;
-    let mut score_count: HashMap<i64, f64> = HashMap::from([
-        (0, 0.0),
-        (1, 0.0),
-        (2, 0.0),
-        (3, 0.0),
-        (4, 0.0),
-        (5, 0.0),
-        (6, 0.0),
-    ]);
+    // Every score in the scoring table is shown, even if it was never reached
+    let max_score = scores
+        .iter()
+        .copied()
+        .chain([game.parser().scoring_table().len() as i64 - 1])
+        .max()
+        .unwrap_or(0);
+    let mut score_count: HashMap<i64, f64> = (0..=max_score).map(|score| (score, 0.0)).collect();
     for score in scores {
         *score_count.get_mut(&score).unwrap() += 1.0;
     }
     response.push_bold_line("Gissningsfördelning:");
-    for score in [0, 1, 2, 3, 4, 5, 6] {
+    for score in 0..=max_score {
         let ratio = 50.0 * score_count[&score] / total;
         response.push_line(format!(
             "{}\t|\t{}|\t{}",
@@ -61,7 +92,14 @@ pub(crate) async fn run(
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
+    register_game_option(command)
         .name("stats")
         .description("Lite statistik om en spelare.")
+        .create_option(|option| {
+            option
+                .name("rating")
+                .description("visa även ratingen över alla spel.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
 }
@@ -0,0 +1,132 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use serenity::model::prelude::Member;
+
+use crate::{
+    scoring::Scoring,
+    season::Period,
+    utils::{local_date, time_zone},
+};
+
+/// The language the bot announces cups and shows the leaders in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Language {
+    #[default]
+    Swedish,
+    English,
+}
+
+impl Language {
+    pub(crate) const ALL: [Language; 2] = [Language::Swedish, Language::English];
+
+    /// Identifier used in the database and as command option value
+    pub(crate) fn value(&self) -> &'static str {
+        match self {
+            Language::Swedish => "sv",
+            Language::English => "en",
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::Swedish => f.write_str("svenska"),
+            Language::English => f.write_str("engelska"),
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.value() == s)
+            .ok_or_else(|| anyhow!("Unknown language {s}"))
+    }
+}
+
+/// The settings of a guild, set with /config. Guilds that never changed them get the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GuildConfig {
+    /// Results only count in this channel, which also shows the leaders in its topic
+    pub(crate) tracked_channel: Option<i64>,
+    /// Where cups are announced, the tracked channel or the latest result channel unless set
+    pub(crate) announcement_channel: Option<i64>,
+    /// The rule of cups without one of their own
+    pub(crate) scoring: Scoring,
+    /// The length of the seasons that are started automatically
+    pub(crate) period: Period,
+    pub(crate) time_zone: Tz,
+    pub(crate) language: Language,
+    /// Whether results get medal reactions
+    pub(crate) reactions: bool,
+    /// Members with this role may run the admin commands, as may administrators
+    pub(crate) admin_role: Option<i64>,
+    /// The points lost for every day of a cup a player skipped, skipped days do not count
+    /// unless set
+    pub(crate) missed_day_penalty: Option<u32>,
+    /// Whether the grid of a result has to agree with its score
+    pub(crate) grid_validation: bool,
+    /// Extra points for every solved game played in hard mode
+    pub(crate) hard_mode_bonus: u32,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        GuildConfig {
+            tracked_channel: None,
+            announcement_channel: None,
+            scoring: Scoring::default(),
+            period: Period::configured(),
+            time_zone: time_zone(),
+            language: Language::default(),
+            reactions: true,
+            admin_role: None,
+            missed_day_penalty: None,
+            grid_validation: true,
+            hard_mode_bonus: 0,
+        }
+    }
+}
+
+impl GuildConfig {
+    // The date in the guild at the given moment, what days and cups are counted by
+    pub(crate) fn date(&self, unixtime: i64) -> NaiveDate {
+        local_date(unixtime, self.time_zone)
+    }
+
+    pub(crate) fn today(&self) -> NaiveDate {
+        self.date(Utc::now().timestamp())
+    }
+
+    // Administrators of the guild, and members with its admin role
+    pub(crate) fn is_admin(&self, member: &Member) -> bool {
+        let administrator = member
+            .permissions
+            .is_some_and(|permissions| permissions.administrator());
+        let admin_role = self
+            .admin_role
+            .is_some_and(|admin_role| member.roles.iter().any(|role| role.0 as i64 == admin_role));
+        administrator || admin_role
+    }
+
+    // The extra points of a result, the hard mode bonus when played in hard mode
+    pub(crate) fn bonus(&self, hard_mode: bool) -> u32 {
+        match hard_mode {
+            true => self.hard_mode_bonus,
+            false => 0,
+        }
+    }
+
+    // Results posted elsewhere than the tracked channel are ignored
+    pub(crate) fn is_tracked(&self, channel_id: i64) -> bool {
+        self.tracked_channel
+            .is_none_or(|tracked| tracked == channel_id)
+    }
+}
@@ -1,24 +1,31 @@
 use std::{
-    collections::HashMap,
-    future::Future,
+    collections::{BTreeMap, HashMap},
     ops::Deref,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use chrono::NaiveDate;
 use log::debug;
+use serenity::model::prelude::MessageId;
 
 use crate::{
-    command::score::FIB,
+    command::score::omni_points,
+    config::GuildConfig,
+    parser::{Game, GameResult},
     player::Player,
+    rating::{outcome, Rating},
     scoresheet::Scoresheet,
-    utils::{self, current_cup_number},
+    scoring::{standings, winners, Ranking, Record, Scoring, ScoringRule, TieBreaker},
+    season::{Period, Season},
 };
 
 pub(crate) struct CachedDatabase {
     database: Database,
-    // Cache
-    current_cup_score: Vec<(Player, u32)>,
-    total_cup_score: Vec<(Player, u32)>,
+    // Cache, per guild and game
+    current_cup_score: HashMap<(i64, Game), Vec<(Player, u32)>>,
+    current_hard_mode_cup_score: HashMap<(i64, Game), Vec<(Player, u32)>>,
+    total_cup_score: HashMap<(i64, Game), Vec<(Player, u32)>>,
+    current_omnicup_score: HashMap<i64, Vec<(Player, u32)>>,
 }
 
 impl Deref for CachedDatabase {
@@ -32,29 +39,67 @@ impl Deref for CachedDatabase {
 
 impl CachedDatabase {
     pub(crate) async fn new(filename: &str) -> Result<Self> {
-        let database = Database::new(filename).await?;
-        let current_cup_score = database.current_cup_score().await?;
-        let total_cup_score = database.total().await?;
-        Ok(Self {
-            database,
-            current_cup_score,
-            total_cup_score,
-        })
+        let mut database = Self {
+            database: Database::new(filename).await?,
+            current_cup_score: HashMap::new(),
+            current_hard_mode_cup_score: HashMap::new(),
+            total_cup_score: HashMap::new(),
+            current_omnicup_score: HashMap::new(),
+        };
+        for guild_id in database.get_guilds().await? {
+            database.update_cache(guild_id).await?;
+        }
+        Ok(database)
     }
 
-    pub(crate) async fn update_cache(&mut self) -> Result<()> {
-        debug!("Updating cache");
-        self.total_cup_score = self.database.total().await?;
-        self.current_cup_score = self.database.current_cup_score().await?;
+    pub(crate) async fn update_cache(&mut self, guild_id: i64) -> Result<()> {
+        debug!("Updating cache of guild {guild_id}");
+        for game in Game::ALL {
+            let total = self
+                .database
+                .total(guild_id, game, None, Ranking::Sum)
+                .await?;
+            self.total_cup_score.insert((guild_id, game), total);
+            let current = self.database.current_cup_score(guild_id, game).await?;
+            self.current_cup_score.insert((guild_id, game), current);
+            let hard_mode = self
+                .database
+                .current_hard_mode_cup_score(guild_id, game)
+                .await?;
+            self.current_hard_mode_cup_score
+                .insert((guild_id, game), hard_mode);
+        }
+        let omnicup = self.database.current_omnicup_score(guild_id).await?;
+        self.current_omnicup_score.insert(guild_id, omnicup);
         Ok(())
     }
 
-    pub(crate) fn current_cup_score(&self) -> &[(Player, u32)] {
-        self.current_cup_score.as_ref()
+    pub(crate) fn current_cup_score(&self, guild_id: i64, game: Game) -> &[(Player, u32)] {
+        self.current_cup_score
+            .get(&(guild_id, game))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn current_hard_mode_cup_score(
+        &self,
+        guild_id: i64,
+        game: Game,
+    ) -> &[(Player, u32)] {
+        self.current_hard_mode_cup_score
+            .get(&(guild_id, game))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn total_cup_score(&self, guild_id: i64, game: Game) -> &[(Player, u32)] {
+        self.total_cup_score
+            .get(&(guild_id, game))
+            .map_or(&[], Vec::as_slice)
     }
 
-    pub(crate) fn total_cup_score(&self) -> &Vec<(Player, u32)> {
-        &self.total_cup_score
+    pub(crate) fn current_omnicup_score(&self, guild_id: i64) -> &[(Player, u32)] {
+        self.current_omnicup_score
+            .get(&guild_id)
+            .map_or(&[], Vec::as_slice)
     }
 }
 
@@ -78,20 +123,116 @@ impl Database {
         Ok(Self { database })
     }
 
-    pub async fn get_daily_day(&self) -> Result<i64> {
-        sqlx::query!("SELECT max(id) as id from daily")
-            .fetch_one(&self.database)
-            .await
-            .map(|row| row.id.into())
-            .map_err(|err| err.into())
+    // Starts a unit of work, see UnitOfWork
+    pub async fn begin(&self) -> Result<UnitOfWork> {
+        Ok(UnitOfWork {
+            transaction: self.database.begin().await?,
+        })
+    }
+
+    // Every guild that has played, by the seasons it has
+    pub async fn get_guilds(&self) -> Result<Vec<i64>> {
+        let res = sqlx::query!("SELECT DISTINCT guild_id FROM season")
+            .fetch_all(&self.database)
+            .await?
+            .into_iter()
+            .map(|row| row.guild_id)
+            .collect();
+        Ok(res)
+    }
+
+    // The channel the guild last posted a result in, where its cups are announced
+    pub async fn get_result_channel(&self, guild_id: i64) -> Result<Option<i64>> {
+        let res = sqlx::query!(
+            "SELECT channel_id FROM score_sheet WHERE guild_id = ? ORDER BY msg_id DESC LIMIT 1",
+            guild_id
+        )
+        .fetch_optional(&self.database)
+        .await?
+        .map(|row| row.channel_id);
+        Ok(res)
+    }
+
+    // Every channel that has been read, posted results in or is tracked, with the newest
+    // message read. Channels without a mark continue after their newest result, tracked
+    // channels never read start from the beginning.
+    pub async fn get_backfill_channels(&self) -> Result<Vec<(i64, i64, i64)>> {
+        let res = sqlx::query!(
+            r#"SELECT guild_id AS "guild_id!", channel_id AS "channel_id!", max(msg_id) AS "msg_id!: i64" FROM (
+                SELECT guild_id, channel_id, last_msg_id AS msg_id FROM channel_backfill
+                UNION ALL SELECT guild_id, channel_id, msg_id FROM score_sheet
+                UNION ALL SELECT guild_id, tracked_channel_id, 0 FROM guild_config
+                    WHERE tracked_channel_id IS NOT NULL
+            ) GROUP BY guild_id, channel_id"#
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| (row.guild_id, row.channel_id, row.msg_id))
+        .collect();
+        Ok(res)
+    }
+
+    pub async fn get_backfill_mark(&self, channel_id: i64) -> Result<Option<i64>> {
+        let res = sqlx::query!(
+            r#"SELECT max(msg_id) AS "msg_id: i64" FROM (
+                SELECT last_msg_id AS msg_id FROM channel_backfill WHERE channel_id = ?
+                UNION ALL SELECT msg_id FROM score_sheet WHERE channel_id = ?
+            )"#,
+            channel_id,
+            channel_id
+        )
+        .fetch_one(&self.database)
+        .await?
+        .msg_id;
+        Ok(res)
+    }
+
+    // Marks the channel as read up to the message, marks never move back
+    pub async fn set_backfill_mark(
+        &self,
+        guild_id: i64,
+        channel_id: i64,
+        msg_id: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO channel_backfill (channel_id, guild_id, last_msg_id) VALUES (?, ?, ?)
+            ON CONFLICT(channel_id) DO UPDATE SET last_msg_id = max(last_msg_id, excluded.last_msg_id)",
+            channel_id,
+            guild_id,
+            msg_id
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
     }
 
-    pub async fn get_gold_medalist(&self, day: Option<i64>) -> Result<Option<Vec<Scoresheet>>> {
+    pub async fn get_daily_day(&self, guild_id: i64, game: Game) -> Result<i64> {
+        let game = game.name();
+        // A game without any days yet has no medalists on day zero either
+        sqlx::query!(
+            r#"SELECT COALESCE(max(id), 0) as "id!: i64" from daily WHERE guild_id = ? AND game = ?"#,
+            guild_id,
+            game
+        )
+        .fetch_one(&self.database)
+        .await
+        .map(|row| row.id)
+        .map_err(|err| err.into())
+    }
+
+    pub async fn get_gold_medalist(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: Option<i64>,
+    ) -> Result<Option<Vec<Scoresheet>>> {
         let day = match day {
             Some(day) => day,
-            None => self.get_daily_day().await?,
+            None => self.get_daily_day(guild_id, game).await?,
         };
-        let medalist: Vec<Scoresheet> = sqlx::query_as!(Scoresheet, "SELECT * from score_sheet WHERE day = ? AND score = (SELECT gold from daily where id = ?)", day, day)
+        let game = game.name();
+        let medalist: Vec<Scoresheet> = sqlx::query_as!(Scoresheet, r#"SELECT id AS "id!", msg_id, guild_id, channel_id, day, player_id, score, season_id, grid, hard_mode, deleted_at, game from score_sheet WHERE guild_id = ? AND game = ? AND day = ? AND score = (SELECT gold from daily where guild_id = ? AND game = ? AND id = ?) AND deleted_at IS NULL"#, guild_id, game, day, guild_id, game, day)
             .fetch_all(&self.database)
             .await?;
         Ok(match medalist.is_empty() {
@@ -100,12 +241,18 @@ impl Database {
         })
     }
 
-    pub async fn get_silver_medalist(&self, day: Option<i64>) -> Result<Option<Vec<Scoresheet>>> {
+    pub async fn get_silver_medalist(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: Option<i64>,
+    ) -> Result<Option<Vec<Scoresheet>>> {
         let day = match day {
             Some(day) => day,
-            None => self.get_daily_day().await?,
+            None => self.get_daily_day(guild_id, game).await?,
         };
-        let medalist: Vec<_> = sqlx::query_as!(Scoresheet, "SELECT * from score_sheet WHERE day = ? AND score = (SELECT silver from daily where id = ?)", day, day)
+        let game = game.name();
+        let medalist: Vec<_> = sqlx::query_as!(Scoresheet, r#"SELECT id AS "id!", msg_id, guild_id, channel_id, day, player_id, score, season_id, grid, hard_mode, deleted_at, game from score_sheet WHERE guild_id = ? AND game = ? AND day = ? AND score = (SELECT silver from daily where guild_id = ? AND game = ? AND id = ?) AND deleted_at IS NULL"#, guild_id, game, day, guild_id, game, day)
         .fetch_all(&self.database)
         .await?;
         Ok(match medalist.is_empty() {
@@ -113,12 +260,18 @@ impl Database {
             false => Some(medalist),
         })
     }
-    pub async fn get_bronze_medalist(&self, day: Option<i64>) -> Result<Option<Vec<Scoresheet>>> {
+    pub async fn get_bronze_medalist(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: Option<i64>,
+    ) -> Result<Option<Vec<Scoresheet>>> {
         let day = match day {
             Some(day) => day,
-            None => self.get_daily_day().await?,
+            None => self.get_daily_day(guild_id, game).await?,
         };
-        let medalist: Vec<_> = sqlx::query_as!(Scoresheet, "SELECT * from score_sheet WHERE day = ? AND score = (SELECT bronze from daily where id = ?)", day, day)
+        let game = game.name();
+        let medalist: Vec<_> = sqlx::query_as!(Scoresheet, r#"SELECT id AS "id!", msg_id, guild_id, channel_id, day, player_id, score, season_id, grid, hard_mode, deleted_at, game from score_sheet WHERE guild_id = ? AND game = ? AND day = ? AND score = (SELECT bronze from daily where guild_id = ? AND game = ? AND id = ?) AND deleted_at IS NULL"#, guild_id, game, day, guild_id, game, day)
         .fetch_all(&self.database)
         .await?;
         Ok(match medalist.is_empty() {
@@ -127,18 +280,75 @@ impl Database {
         })
     }
 
-    pub async fn get_daily_high_scores(&self, day: i64) -> Result<[Option<i64>; 3]> {
-        let scores = sqlx::query!("SELECT gold, silver, bronze FROM daily WHERE id = ?", day)
-            .fetch_one(&self.database)
+    pub async fn get_score_sheet(&self, msg_id: i64) -> Result<Option<Scoresheet>> {
+        let mut connection = self.database.acquire().await?;
+        select_score_sheet(&mut connection, msg_id).await
+    }
+
+    // The counted result of the player on the day, whichever message it was posted in
+    pub async fn get_player_score_sheet(
+        &self,
+        guild_id: i64,
+        player_id: i64,
+        game: Game,
+        day: i64,
+    ) -> Result<Option<Scoresheet>> {
+        let game = game.name();
+        let mut connection = self.database.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT msg_id FROM score_sheet WHERE guild_id = ? AND player_id = ? AND game = ? AND day = ? AND deleted_at IS NULL",
+            guild_id,
+            player_id,
+            game,
+            day
+        )
+        .fetch_optional(&mut connection)
+        .await?;
+        match row {
+            Some(row) => select_score_sheet(&mut connection, row.msg_id).await,
+            None => Ok(None),
+        }
+    }
+
+    // Whether the message has been counted, even if it has been retracted since
+    pub async fn is_counted(&self, msg_id: i64) -> Result<bool> {
+        let row = sqlx::query!("SELECT id FROM score_sheet WHERE msg_id = ?", msg_id)
+            .fetch_optional(&self.database)
             .await?;
+        Ok(row.is_some())
+    }
+
+    pub async fn get_daily_high_scores(
+        &self,
+        guild_id: i64,
+        game: Game,
+        day: i64,
+    ) -> Result<[Option<i64>; 3]> {
+        let game = game.name();
+        let scores = sqlx::query!(
+            "SELECT gold, silver, bronze FROM daily WHERE guild_id = ? AND game = ? AND id = ?",
+            guild_id,
+            game,
+            day
+        )
+        .fetch_one(&self.database)
+        .await?;
 
         Ok([scores.gold, scores.silver, scores.bronze])
     }
 
-    pub async fn get_user_played_games(&self, user_id: i64) -> Result<i32> {
+    pub async fn get_user_played_games(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        game: Game,
+    ) -> Result<i32> {
+        let game = game.name();
         let res = sqlx::query!(
-            "SELECT COUNT(id) as count FROM score_sheet WHERE player_id = ?",
-            user_id
+            "SELECT COUNT(id) as count FROM score_sheet WHERE guild_id = ? AND player_id = ? AND game = ? AND deleted_at IS NULL",
+            guild_id,
+            user_id,
+            game
         )
         .fetch_one(&self.database)
         .await?
@@ -146,193 +356,2416 @@ impl Database {
         Ok(res)
     }
 
-    pub async fn get_user_gold_medals(&self, user_id: i64) -> Result<i32> {
-        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.gold AND score_sheet.day = daily.id AND score_sheet.player_id = ?", user_id).fetch_one(&self.database).await?.amount;
+    pub async fn get_user_hard_mode_games(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        game: Game,
+    ) -> Result<i32> {
+        let game = game.name();
+        let res = sqlx::query!(
+            "SELECT COUNT(id) as count FROM score_sheet WHERE guild_id = ? AND player_id = ? AND game = ? AND hard_mode AND deleted_at IS NULL",
+            guild_id,
+            user_id,
+            game
+        )
+        .fetch_one(&self.database)
+        .await?
+        .count;
         Ok(res)
     }
 
-    pub async fn get_user_silver_medals(&self, user_id: i64) -> Result<i32> {
-        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.silver AND score_sheet.day = daily.id AND score_sheet.player_id = ?", user_id).fetch_one(&self.database).await?.amount;
+    pub async fn get_user_gold_medals(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        game: Game,
+    ) -> Result<i32> {
+        let game = game.name();
+        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.gold AND score_sheet.guild_id = daily.guild_id AND score_sheet.game = daily.game AND score_sheet.day = daily.id AND score_sheet.guild_id = ? AND score_sheet.player_id = ? AND score_sheet.game = ? AND score_sheet.deleted_at IS NULL", guild_id, user_id, game).fetch_one(&self.database).await?.amount;
         Ok(res)
     }
 
-    pub async fn get_user_bronze_medals(&self, user_id: i64) -> Result<i32> {
-        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.bronze AND score_sheet.day = daily.id AND score_sheet.player_id = ?", user_id).fetch_one(&self.database).await?.amount;
+    pub async fn get_user_silver_medals(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        game: Game,
+    ) -> Result<i32> {
+        let game = game.name();
+        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.silver AND score_sheet.guild_id = daily.guild_id AND score_sheet.game = daily.game AND score_sheet.day = daily.id AND score_sheet.guild_id = ? AND score_sheet.player_id = ? AND score_sheet.game = ? AND score_sheet.deleted_at IS NULL", guild_id, user_id, game).fetch_one(&self.database).await?.amount;
         Ok(res)
     }
 
-    pub async fn get_user_scores(&self, user_id: i64) -> Vec<i64> {
-        // Grab all wordle scores
-        sqlx::query!("SELECT score FROM score_sheet WHERE player_id = ?", user_id)
-            .fetch_all(&self.database)
-            .await
-            .unwrap_or_default()
-            .iter()
-            .map(|r| r.score)
-            .collect()
+    pub async fn get_user_bronze_medals(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        game: Game,
+    ) -> Result<i32> {
+        let game = game.name();
+        let res = sqlx::query!("SELECT COUNT(score) as amount FROM score_sheet JOIN daily ON score_sheet.score = daily.bronze AND score_sheet.guild_id = daily.guild_id AND score_sheet.game = daily.game AND score_sheet.day = daily.id AND score_sheet.guild_id = ? AND score_sheet.player_id = ? AND score_sheet.game = ? AND score_sheet.deleted_at IS NULL", guild_id, user_id, game).fetch_one(&self.database).await?.amount;
+        Ok(res)
     }
 
-    pub async fn new_player(&self, player_id: i64) -> Result<()> {
+    pub async fn get_user_scores(&self, guild_id: i64, user_id: i64, game: Game) -> Vec<i64> {
+        let game = game.name();
         sqlx::query!(
-            "INSERT INTO player (id) VALUES (?) ON CONFLICT DO NOTHING",
-            player_id
+            "SELECT score FROM score_sheet WHERE guild_id = ? AND player_id = ? AND game = ? AND deleted_at IS NULL",
+            guild_id,
+            user_id,
+            game
         )
-        .execute(&self.database)
-        .await?;
-        Ok(())
+        .fetch_all(&self.database)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.score)
+        .collect()
+    }
+
+    // Counts a result posted at the given moment. The player, the day and the season are
+    // created, the score sheet stored and the placements of the day recounted all at once, or
+    // not at all. Returns whether the score sheet was stored.
+    pub async fn ingest(
+        &self,
+        msg_id: i64,
+        guild_id: i64,
+        channel_id: i64,
+        player_id: i64,
+        posted_at: i64,
+        result: &GameResult,
+    ) -> Result<bool> {
+        let config = self.get_config(guild_id).await?;
+        let date = config.date(posted_at);
+        let mut work = self.begin().await?;
+        work.new_player(player_id).await?;
+        work.new_daily(guild_id, result.game, result.day).await?;
+        let season = work.season_for_date(guild_id, date, config.period).await?;
+        debug!(
+            "Guild: {}, Game: {}, Day: {}, Score: {}, Hard mode: {}, Season: {}",
+            guild_id, result.game, result.day, result.score, result.hard_mode, season.name
+        );
+        let stored = work
+            .new_score_sheet(msg_id, guild_id, channel_id, player_id, season.id, result)
+            .await?;
+        work.recompute_daily(guild_id, result.game, result.day)
+            .await?;
+        work.commit().await?;
+        Ok(stored)
     }
 
-    pub async fn new_daily(&self, day: i64) -> Result<()> {
+    // Moves the results of a player in the guild to another, e.g. from a second account.
+    // Results of days both have played are voided. Returns how many were moved and voided.
+    pub async fn merge_players(&self, guild_id: i64, from: i64, into: i64) -> Result<(u64, u64)> {
+        let deleted_at = chrono::Utc::now().timestamp();
+        let mut work = self.begin().await?;
+        work.new_player(into).await?;
+        let voided = sqlx::query!(
+            "UPDATE score_sheet SET deleted_at = ? WHERE guild_id = ? AND player_id = ? AND deleted_at IS NULL
+            AND EXISTS (SELECT 1 FROM score_sheet AS other WHERE other.guild_id = score_sheet.guild_id
+                AND other.player_id = ? AND other.game = score_sheet.game AND other.day = score_sheet.day
+                AND other.deleted_at IS NULL)",
+            deleted_at,
+            guild_id,
+            from,
+            into
+        )
+        .execute(&mut work.transaction)
+        .await?
+        .rows_affected();
+        // A deleted result gives way to the moved one, as it would to a new result
         sqlx::query!(
-            "INSERT INTO daily (id) VALUES (?) ON CONFLICT DO NOTHING",
-            day
+            "DELETE FROM score_sheet WHERE guild_id = ? AND player_id = ? AND deleted_at IS NOT NULL
+            AND EXISTS (SELECT 1 FROM score_sheet AS other WHERE other.guild_id = score_sheet.guild_id
+                AND other.player_id = ? AND other.game = score_sheet.game AND other.day = score_sheet.day
+                AND other.deleted_at IS NULL)",
+            guild_id,
+            into,
+            from
         )
-        .execute(&self.database)
+        .execute(&mut work.transaction)
         .await?;
-        Ok(())
+        let moved = sqlx::query!(
+            "UPDATE score_sheet SET player_id = ? WHERE guild_id = ? AND player_id = ? AND deleted_at IS NULL",
+            into,
+            guild_id,
+            from
+        )
+        .execute(&mut work.transaction)
+        .await?
+        .rows_affected();
+        work.commit().await?;
+        Ok((moved, voided))
     }
 
-    pub async fn new_score_sheet(
+    // The three best distinct scores of a day, derived straight from its remaining score sheets
+    pub async fn derived_high_scores(
         &self,
-        msg_id: i64,
+        guild_id: i64,
+        game: Game,
         day: i64,
-        player_id: i64,
-        score: i64,
-        cup_number: String,
+    ) -> Result<[Option<i64>; 3]> {
+        let mut connection = self.database.acquire().await?;
+        select_derived_high_scores(&mut connection, guild_id, game, day).await
+    }
+
+    pub async fn get_days(&self, guild_id: i64) -> Result<Vec<(Game, i64)>> {
+        sqlx::query!(
+            "SELECT game, id FROM daily WHERE guild_id = ? ORDER BY game, id",
+            guild_id
+        )
+        .fetch_all(&self.database)
+        .await?
+        .iter()
+        .map(|daily| Ok((daily.game.parse()?, daily.id)))
+        .collect()
+    }
+
+    // Rebuilds the high scores of every day of the guild, returns the number of days
+    pub async fn recompute_all_dailies(&self, guild_id: i64) -> Result<usize> {
+        let days = self.get_days(guild_id).await?;
+        let mut work = self.begin().await?;
+        for (game, day) in &days {
+            work.recompute_daily(guild_id, *game, *day).await?;
+        }
+        work.commit().await?;
+        Ok(days.len())
+    }
+
+    // Days where the stored high scores differ from the derived ones, as (game, day, stored, derived)
+    pub async fn inconsistent_dailies(
+        &self,
+        guild_id: i64,
+    ) -> Result<Vec<(Game, i64, [Option<i64>; 3], [Option<i64>; 3])>> {
+        let mut inconsistent = vec![];
+        for (game, day) in self.get_days(guild_id).await? {
+            let stored = self.get_daily_high_scores(guild_id, game, day).await?;
+            let derived = self.derived_high_scores(guild_id, game, day).await?;
+            if stored != derived {
+                inconsistent.push((game, day, stored, derived));
+            }
+        }
+        Ok(inconsistent)
+    }
+
+    pub async fn new_audit_entry(
+        &self,
+        guild_id: i64,
+        actor_id: Option<i64>,
+        action: &str,
+        msg_id: Option<i64>,
+        detail: &str,
+    ) -> Result<()> {
+        let mut connection = self.database.acquire().await?;
+        insert_audit_entry(&mut connection, guild_id, actor_id, action, msg_id, detail).await
+    }
+
+    // The cups of the season that have been announced, by game name or omni
+    pub async fn get_announced_cups(&self, season_id: i64) -> Result<Vec<String>> {
+        let res = sqlx::query!(
+            "SELECT cup FROM announcement WHERE season_id = ?",
+            season_id
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| row.cup)
+        .collect();
+        Ok(res)
+    }
+
+    // Records that the cup is announced, false if that already happened
+    pub async fn new_announcement(
+        &self,
+        season_id: i64,
+        cup: &str,
+        winners: &[Player],
+    ) -> Result<bool> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut transaction = self.database.begin().await?;
+        let res = sqlx::query!(
+            "INSERT INTO announcement (season_id, cup, announced_at) VALUES (?, ?, ?) ON CONFLICT DO NOTHING",
+            season_id,
+            cup,
+            timestamp
+        )
+        .execute(&mut transaction)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+        for winner in winners {
+            let player_id = winner.id as i64;
+            sqlx::query!(
+                "INSERT INTO announcement_winner (season_id, cup, player_id) VALUES (?, ?, ?)",
+                season_id,
+                cup,
+                player_id
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn get_scoring_rule(&self, guild_id: i64, season_id: i64) -> Result<Scoring> {
+        let row = sqlx::query!(
+            "SELECT rule FROM scoring_rule WHERE guild_id = ? AND season_id = ?",
+            guild_id,
+            season_id
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        match row {
+            Some(row) => row.rule.parse(),
+            None => Ok(self.get_config(guild_id).await?.scoring),
+        }
+    }
+
+    pub async fn set_scoring_rule(
+        &self,
+        guild_id: i64,
+        season_id: i64,
+        scoring: Scoring,
     ) -> Result<()> {
-        // Conflict = Cheater
+        let rule = scoring.name();
         sqlx::query!(
-        "INSERT INTO score_sheet (msg_id, day, player_id, score, cup_number) VALUES (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
-        msg_id,
-        day,
-        player_id,
-        score,
-        cup_number,
-    )
-    .execute(&self.database)
-    .await?;
+            "INSERT INTO scoring_rule (guild_id, season_id, rule) VALUES (?, ?, ?)
+            ON CONFLICT(guild_id, season_id) DO UPDATE SET rule = excluded.rule",
+            guild_id,
+            season_id,
+            rule
+        )
+        .execute(&self.database)
+        .await?;
         Ok(())
     }
 
-    pub async fn update_daily(
+    // The rule of the cup, unless another one is asked for
+    async fn resolve_scoring(
         &self,
-        day: i64,
-        gold: Option<i64>,
-        silver: Option<i64>,
-        bronze: Option<i64>,
-    ) -> Result<()> {
+        guild_id: i64,
+        season_id: i64,
+        scoring: Option<Scoring>,
+    ) -> Result<Scoring> {
+        match scoring {
+            Some(scoring) => Ok(scoring),
+            None => self.get_scoring_rule(guild_id, season_id).await,
+        }
+    }
+
+    pub async fn get_config(&self, guild_id: i64) -> Result<GuildConfig> {
+        let row = sqlx::query!(
+            "SELECT tracked_channel_id, announcement_channel_id, scoring_rule, season_period, time_zone, language, reactions, admin_role_id, missed_day_penalty, grid_validation, hard_mode_bonus
+            FROM guild_config WHERE guild_id = ?",
+            guild_id
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        let Some(row) = row else {
+            return Ok(GuildConfig::default());
+        };
+        Ok(GuildConfig {
+            tracked_channel: row.tracked_channel_id,
+            announcement_channel: row.announcement_channel_id,
+            scoring: row.scoring_rule.parse()?,
+            period: row.season_period.parse()?,
+            time_zone: row.time_zone.parse().map_err(|e| anyhow!("{e}"))?,
+            language: row.language.parse()?,
+            reactions: row.reactions,
+            admin_role: row.admin_role_id,
+            missed_day_penalty: row.missed_day_penalty.map(|penalty| penalty as u32),
+            grid_validation: row.grid_validation,
+            hard_mode_bonus: row.hard_mode_bonus as u32,
+        })
+    }
+
+    pub async fn get_cup_ranking(&self, guild_id: i64) -> Result<Ranking> {
+        let row = sqlx::query!(
+            "SELECT ranking, min_games, shrinkage FROM cup_ranking WHERE guild_id = ?",
+            guild_id
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        Ok(match row {
+            Some(row) if row.ranking == "average" => Ranking::Average {
+                min_games: row.min_games as u32,
+                shrinkage: row.shrinkage,
+            },
+            _ => Ranking::Sum,
+        })
+    }
+
+    pub async fn set_cup_ranking(&self, guild_id: i64, ranking: Ranking) -> Result<()> {
+        let (name, min_games, shrinkage) = match ranking {
+            Ranking::Sum => ("sum", 0, false),
+            Ranking::Average {
+                min_games,
+                shrinkage,
+            } => ("average", min_games, shrinkage),
+        };
         sqlx::query!(
-            "UPDATE daily SET (gold, silver, bronze) = (?, ?, ?) WHERE id = ?",
-            gold,
-            silver,
-            bronze,
-            day
+            "INSERT INTO cup_ranking (guild_id, ranking, min_games, shrinkage) VALUES (?, ?, ?, ?)
+            ON CONFLICT(guild_id) DO UPDATE SET (ranking, min_games, shrinkage) = (excluded.ranking, excluded.min_games, excluded.shrinkage)",
+            guild_id,
+            name,
+            min_games,
+            shrinkage
         )
         .execute(&self.database)
         .await?;
         Ok(())
     }
 
-    pub async fn get_players(&self) -> Result<Vec<i64>> {
-        let res = sqlx::query!("SELECT id FROM player")
-            .fetch_all(&self.database)
-            .await?
-            .iter()
-            .map(|player| player.id)
-            .collect();
-        Ok(res)
+    pub async fn get_tie_breakers(&self, guild_id: i64) -> Result<Vec<TieBreaker>> {
+        let row = sqlx::query!(
+            "SELECT tie_breakers FROM tie_breaker WHERE guild_id = ?",
+            guild_id
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        match row {
+            Some(row) => row
+                .tie_breakers
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::parse)
+                .collect(),
+            None => Ok(vec![]),
+        }
     }
 
-    pub async fn get_player_scores_for_current_cup(&self, player_id: i64) -> Result<Vec<i64>> {
-        let cup_number = current_cup_number();
-        self.get_player_scores_for_cup_number(player_id, &cup_number)
-            .await
+    pub async fn set_tie_breakers(&self, guild_id: i64, tie_breakers: &[TieBreaker]) -> Result<()> {
+        let names: Vec<&str> = tie_breakers.iter().map(TieBreaker::name).collect();
+        let names = names.join(",");
+        sqlx::query!(
+            "INSERT INTO tie_breaker (guild_id, tie_breakers) VALUES (?, ?)
+            ON CONFLICT(guild_id) DO UPDATE SET tie_breakers = excluded.tie_breakers",
+            guild_id,
+            names
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    // Every score posted for the puzzle, what a result is compared against
+    pub async fn get_day_scores(&self, guild_id: i64, game: Game, day: i64) -> Result<Vec<i64>> {
+        let game = game.name();
+        let res = sqlx::query!(
+            "SELECT score FROM score_sheet WHERE guild_id = ? AND game = ? AND day = ? AND deleted_at IS NULL",
+            guild_id,
+            game,
+            day
+        )
+        .fetch_all(&self.database)
+        .await?
+        .iter()
+        .map(|row| row.score)
+        .collect();
+        Ok(res)
     }
 
-    pub async fn get_player_scores_for_cup_number(
+    // The results of a game in a cup as (player_id, day, score, hard_mode)
+    async fn get_cup_results(
         &self,
-        player_id: i64,
-        cup_number: &str,
-    ) -> Result<Vec<i64>> {
-        debug!("Fetching score sheets for cup number {cup_number}");
+        game: Game,
+        season_id: i64,
+    ) -> Result<Vec<(i64, i64, i64, bool)>> {
+        debug!("Fetching {game} score sheets for season {season_id}");
+        let game = game.name();
+        let res = sqlx::query!(
+            "SELECT player_id, day, score, hard_mode FROM score_sheet WHERE game = ? AND season_id = ? AND deleted_at IS NULL",
+            game,
+            season_id
+        )
+        .fetch_all(&self.database)
+        .await?
+        .iter()
+        .map(|row| (row.player_id, row.day, row.score, row.hard_mode))
+        .collect();
+        Ok(res)
+    }
+
+    // When every result of the cup was posted, as the message id
+    async fn get_posting_times(&self, season_id: i64) -> Result<HashMap<(i64, Game, i64), i64>> {
         sqlx::query!(
-            "SELECT score FROM score_sheet WHERE player_id = ? AND cup_number = ?",
-            player_id,
-            cup_number
+            "SELECT player_id, game, day, msg_id FROM score_sheet WHERE season_id = ? AND deleted_at IS NULL",
+            season_id
         )
         .fetch_all(&self.database)
-        .await
-        .map(|row| row.iter().map(|score_sheet| score_sheet.score).collect())
-        .map_err(|err| err.into())
+        .await?
+        .into_iter()
+        .map(|row| Ok(((row.player_id, row.game.parse()?, row.day), row.msg_id)))
+        .collect()
     }
 
-    // Calculate the current score of all players
-    // The wordle scores are used as indexes into the fibonachi sequence
-    // to lend more weight to earlier correct guesses
-    // TODO: Factor out database to make it testable
-    async fn calculate_leader_board<F, Fut>(&self, score_getter: F) -> Result<Vec<(Player, u32)>>
-    where
-        F: Fn(i64) -> Fut,
-        Fut: Future<Output = Result<Vec<i64>>>,
-    {
-        debug!("Calculating leader board");
-        let mut fetched: usize = 0;
-        let mut leader_board: Vec<(Player, u32)> = vec![];
-        let players = self.get_players().await?;
-        for player_id in players {
-            let scores = score_getter(player_id).await?;
-            fetched += scores.len();
-            let score = scores.iter().fold(0, |acc, elem| acc + FIB[*elem as usize]);
-            if score == 0 {
-                continue;
-            }
-            leader_board.push((Player::from(player_id), score));
-        }
-        debug!("Fetched {fetched} score sheets.");
-        // XXX
-        leader_board.sort_by_key(|x| x.1);
-        leader_board.reverse();
-        Ok(leader_board)
+    pub async fn get_season_by_name(&self, guild_id: i64, name: &str) -> Result<Option<Season>> {
+        let mut connection = self.database.acquire().await?;
+        select_season_by_name(&mut connection, guild_id, name).await
     }
 
-    async fn total(&self) -> Result<Vec<(Player, u32)>> {
-        let rows = sqlx::query!("SELECT player_id, score FROM score_sheet ORDER BY player_id")
-            .fetch_all(&self.database)
-            .await?;
-        let mut result: HashMap<Player, u32> = HashMap::new();
-        for (player, score) in rows
-            .iter()
-            .map(|row| (Player::from(row.player_id), row.score))
-        {
-            result
-                .entry(player)
-                .and_modify(|total_score| *total_score += FIB[score as usize])
-                .or_insert(FIB[score as usize]);
+    // Every season of the guild, oldest first
+    pub async fn get_seasons(&self, guild_id: i64) -> Result<Vec<Season>> {
+        let mut seasons = sqlx::query!(
+            r#"SELECT id AS "id!", name, start_date, end_date FROM season WHERE guild_id = ?"#,
+            guild_id
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| Season::from_columns(row.id, row.name, &row.start_date, &row.end_date))
+        .collect::<Result<Vec<Season>>>()?;
+        seasons.sort();
+        Ok(seasons)
+    }
+
+    // The season of the guild on the date, see UnitOfWork::season_for_date. Only a missing
+    // season takes a unit of work to create.
+    pub async fn season_for_date(&self, guild_id: i64, date: NaiveDate) -> Result<Season> {
+        let mut connection = self.database.acquire().await?;
+        if let Some(season) = select_season_on(&mut connection, guild_id, date).await? {
+            return Ok(season);
         }
-        let result: Vec<(Player, u32)> = result.iter().map(|(k, v)| (*k, *v)).collect();
-        Ok(result)
+        drop(connection);
+        let period = self.get_config(guild_id).await?.period;
+        let mut work = self.begin().await?;
+        let season = work.season_for_date(guild_id, date, period).await?;
+        work.commit().await?;
+        Ok(season)
     }
 
-    async fn current_cup_score(&self) -> Result<Vec<(Player, u32)>> {
-        let score_getter =
-            |player_id| async move { self.get_player_scores_for_current_cup(player_id).await };
-        self.calculate_leader_board(score_getter).await
+    pub async fn current_season(&self, guild_id: i64) -> Result<Season> {
+        let today = self.get_config(guild_id).await?.today();
+        self.season_for_date(guild_id, today).await
     }
 
-    pub async fn cup_leader(&self, cup_number: &str) -> Result<Option<Player>> {
-        let score_getter = |player_id| async move {
-            self.get_player_scores_for_cup_number(player_id, cup_number)
-                .await
-        };
-        Ok(self
-            .calculate_leader_board(score_getter)
-            .await?
-            .first()
-            .map(|x| x.0))
+    // A custom season, which may only start after today. The season it starts in is cut short.
+    pub async fn new_season(
+        &self,
+        guild_id: i64,
+        name: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Season> {
+        if end < start {
+            bail!("Säsongen slutar innan den börjar");
+        }
+        if start <= self.get_config(guild_id).await?.today() {
+            bail!("Säsonger kan bara läggas till framåt i tiden");
+        }
+        if self.get_season_by_name(guild_id, name).await?.is_some() {
+            bail!("Det finns redan en säsong som heter {name}");
+        }
+        let (start_date, end_date) = (start.to_string(), end.to_string());
+        let overlapping = sqlx::query!(
+            r#"SELECT id AS "id!", name, start_date, end_date FROM season WHERE guild_id = ? AND start_date <= ? AND end_date >= ?"#,
+            guild_id,
+            end_date,
+            start_date
+        )
+        .fetch_all(&self.database)
+        .await?;
+        // Only the end of an earlier season can give way
+        let conflict = overlapping
+            .iter()
+            .find(|row| row.start_date >= start_date || row.end_date > end_date);
+        if let Some(season) = conflict {
+            bail!("Säsongen överlappar {}", season.name);
+        }
+
+        let mut transaction = self.database.begin().await?;
+        let cut_short = start.pred_opt().unwrap().to_string();
+        for season in overlapping {
+            debug!("Ending season {} at {cut_short}", season.name);
+            sqlx::query!(
+                "UPDATE season SET end_date = ? WHERE id = ?",
+                cut_short,
+                season.id
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        let id = sqlx::query!(
+            "INSERT INTO season (guild_id, name, start_date, end_date) VALUES (?, ?, ?, ?)",
+            guild_id,
+            name,
+            start_date,
+            end_date
+        )
+        .execute(&mut transaction)
+        .await?
+        .last_insert_rowid();
+        transaction.commit().await?;
+        Ok(Season {
+            id,
+            name: name.to_string(),
+            start,
+            end,
+        })
     }
 
-    pub async fn current_cup_leader(&self) -> Result<Option<Player>> {
-        let current_cup = utils::current_cup_number();
-        self.cup_leader(&current_cup).await
+    // Replays every puzzle of the guild as a match among everyone who posted it, one rating
+    // period per day. A puzzle belongs to the day its first result was posted.
+    pub async fn recompute_ratings(&self, guild_id: i64) -> Result<()> {
+        let rows = sqlx::query!(
+            "SELECT player_id, game, day, score, msg_id FROM score_sheet WHERE guild_id = ? AND deleted_at IS NULL",
+            guild_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+        let mut matches: HashMap<(String, i64), Match> = HashMap::new();
+        for row in rows {
+            let result = (row.player_id, row.score, row.msg_id);
+            matches.entry((row.game, row.day)).or_default().push(result);
+        }
+        let config = self.get_config(guild_id).await?;
+        let mut periods: BTreeMap<NaiveDate, Vec<Match>> = BTreeMap::new();
+        for results in matches.into_values() {
+            let posted = results.iter().map(|(_, _, msg_id)| *msg_id).min().unwrap();
+            let date = config.date(MessageId(posted as u64).created_at().unix_timestamp());
+            periods.entry(date).or_default().push(results);
+        }
+
+        let mut ratings: HashMap<i64, Rating> = HashMap::new();
+        let mut history: Vec<(i64, NaiveDate, Rating)> = vec![];
+        if let (Some(first), Some(last)) = (periods.keys().next(), periods.keys().last()) {
+            // Days without any puzzles still let the deviation of everyone grow
+            for date in first.iter_days().take_while(|date| date <= last) {
+                let mut games: HashMap<i64, Vec<(Rating, f64)>> = HashMap::new();
+                for results in periods.get(&date).into_iter().flatten() {
+                    for (player_id, score, _) in results {
+                        let player_games = games.entry(*player_id).or_default();
+                        for (opponent_id, other, _) in results {
+                            if opponent_id == player_id {
+                                continue;
+                            }
+                            let opponent = ratings.get(opponent_id).copied().unwrap_or_default();
+                            player_games.push((opponent, outcome(*score, *other)));
+                        }
+                    }
+                }
+                for player_id in games.keys() {
+                    ratings.entry(*player_id).or_default();
+                }
+                // Everyone is rated against the ratings from before the period
+                ratings = ratings
+                    .iter()
+                    .map(|(player_id, rating)| {
+                        let player_games = games.get(player_id).map_or(&[][..], Vec::as_slice);
+                        (*player_id, rating.update(player_games))
+                    })
+                    .collect();
+                for player_id in games.keys() {
+                    history.push((*player_id, date, ratings[player_id]));
+                }
+            }
+        }
+
+        debug!("Storing {} ratings", history.len());
+        let mut transaction = self.database.begin().await?;
+        sqlx::query!("DELETE FROM rating_history WHERE guild_id = ?", guild_id)
+            .execute(&mut transaction)
+            .await?;
+        for (player_id, date, rating) in history {
+            let date = date.to_string();
+            sqlx::query!(
+                "INSERT INTO rating_history (guild_id, player_id, date, rating, deviation, volatility) VALUES (?, ?, ?, ?, ?, ?)",
+                guild_id,
+                player_id,
+                date,
+                rating.rating,
+                rating.deviation,
+                rating.volatility
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    // The latest rating of every player in the guild as of today, best first
+    pub async fn get_ratings(&self, guild_id: i64) -> Result<Vec<(Player, Rating)>> {
+        let rows = sqlx::query!(
+            r#"SELECT player_id, date, rating AS "rating: f64", deviation AS "deviation: f64",
+            volatility AS "volatility: f64" FROM rating_history AS latest
+            WHERE guild_id = ? AND date = (SELECT max(date) FROM rating_history
+                WHERE guild_id = latest.guild_id AND player_id = latest.player_id)"#,
+            guild_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+        let today = self.get_config(guild_id).await?.today();
+        let mut ratings = vec![];
+        for row in rows {
+            let date: NaiveDate = row.date.parse()?;
+            let mut rating = Rating {
+                rating: row.rating,
+                deviation: row.deviation,
+                volatility: row.volatility,
+            };
+            // The deviation grows for every day skipped since
+            for _ in date.iter_days().skip(1).take_while(|date| *date <= today) {
+                rating = rating.idle();
+            }
+            ratings.push((Player::from(row.player_id), rating));
+        }
+        ratings.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating));
+        Ok(ratings)
+    }
+
+    pub async fn get_player_rating(&self, guild_id: i64, player_id: i64) -> Result<Option<Rating>> {
+        Ok(self
+            .get_ratings(guild_id)
+            .await?
+            .into_iter()
+            .find(|(player, _)| player.id as i64 == player_id)
+            .map(|(_, rating)| rating))
+    }
+
+    // Points of every player in one cup, under the rule they were scored by
+    async fn cup_points(
+        &self,
+        guild_id: i64,
+        game: Game,
+        season_id: i64,
+        hard_mode: bool,
+        scoring: Option<Scoring>,
+    ) -> Result<(Scoring, HashMap<i64, Vec<u32>>, HashMap<i64, u32>)> {
+        let scoring = self.resolve_scoring(guild_id, season_id, scoring).await?;
+        let config = self.get_config(guild_id).await?;
+        let results = self.cup_results(game, season_id, hard_mode).await?;
+        let mut points = calculate_points(scoring.rule(), game, &results, &config);
+        let mut penalties = HashMap::new();
+        // Skipped days are results without points that lose the penalty on top. They are
+        // left out of the fields, so nobody else gains from them. The hard mode cup only
+        // counts what was played.
+        if let Some(penalty) = config.missed_day_penalty.filter(|_| !hard_mode) {
+            let missed = self
+                .missed_days(guild_id, game, season_id, &results)
+                .await?;
+            for (player_id, days) in missed {
+                let player_points = points.entry(player_id).or_default();
+                player_points.extend(std::iter::repeat_n(0, days as usize));
+                penalties.insert(player_id, days * penalty);
+            }
+        }
+        Ok((scoring, points, penalties))
+    }
+
+    // The results counting towards one cup
+    async fn cup_results(
+        &self,
+        game: Game,
+        season_id: i64,
+        hard_mode: bool,
+    ) -> Result<Vec<(i64, i64, i64, bool)>> {
+        let mut results = self.get_cup_results(game, season_id).await?;
+        // Only games played in hard mode count towards the hard mode cup
+        if hard_mode {
+            results.retain(|(_, _, _, hard_mode)| *hard_mode);
+        }
+        Ok(results)
+    }
+
+    // How many days of the cup every player skipped, of the days someone played. Players get
+    // grace until their first post, and the newest day can still be played.
+    async fn missed_days(
+        &self,
+        guild_id: i64,
+        game: Game,
+        season_id: i64,
+        results: &[(i64, i64, i64, bool)],
+    ) -> Result<HashMap<i64, u32>> {
+        let newest_day = self.get_daily_day(guild_id, game).await?;
+        let game_name = game.name();
+        let days: Vec<i64> = sqlx::query!(
+            "SELECT id FROM daily WHERE guild_id = ? AND game = ? AND id < ? AND id IN (SELECT day FROM score_sheet WHERE game = ? AND season_id = ? AND deleted_at IS NULL) ORDER BY id",
+            guild_id,
+            game_name,
+            newest_day,
+            game_name,
+            season_id
+        )
+        .fetch_all(&self.database)
+        .await?
+        .iter()
+        .map(|row| row.id)
+        .collect();
+        let first_days = sqlx::query!(
+            r#"SELECT player_id, min(day) AS "first_day!: i64" FROM score_sheet WHERE guild_id = ? AND game = ? AND deleted_at IS NULL GROUP BY player_id"#,
+            guild_id,
+            game_name
+        )
+        .fetch_all(&self.database)
+        .await?;
+        let mut missed = HashMap::new();
+        for player in first_days {
+            for day in days.iter().filter(|day| **day >= player.first_day) {
+                let played = results.iter().any(|(player_id, played_day, _, _)| {
+                    *player_id == player.player_id && played_day == day
+                });
+                if !played {
+                    *missed.entry(player.player_id).or_default() += 1;
+                }
+            }
+        }
+        Ok(missed)
+    }
+
+    // Score of every player in one cup
+    pub async fn cup_score(
+        &self,
+        guild_id: i64,
+        game: Game,
+        season_id: i64,
+        hard_mode: bool,
+        scoring: Option<Scoring>,
+        ranking: Ranking,
+    ) -> Result<Vec<(Player, u32)>> {
+        let (scoring, points, penalties) = self
+            .cup_points(guild_id, game, season_id, hard_mode, scoring)
+            .await?;
+        Ok(ranking.rank(scoring.rule(), &points, &penalties))
+    }
+
+    // Every cup is scored under its own rule, unless another one is asked for
+    pub async fn total(
+        &self,
+        guild_id: i64,
+        game: Game,
+        scoring: Option<Scoring>,
+        ranking: Ranking,
+    ) -> Result<Vec<(Player, u32)>> {
+        let mut result: HashMap<i64, Vec<u32>> = HashMap::new();
+        let mut result_penalties: HashMap<i64, u32> = HashMap::new();
+        for season in self.get_seasons(guild_id).await? {
+            let (scoring, points, penalties) = self
+                .cup_points(guild_id, game, season.id, false, scoring)
+                .await?;
+            for (player_id, points) in points {
+                let penalty = penalties.get(&player_id).copied().unwrap_or(0);
+                // A sum adds up the totals of the cups, an average needs every game
+                let points = match ranking {
+                    Ranking::Sum => vec![scoring.rule().penalised_total(&points, penalty)],
+                    Ranking::Average { .. } => {
+                        *result_penalties.entry(player_id).or_default() += penalty;
+                        points
+                    }
+                };
+                result.entry(player_id).or_default().extend(points);
+            }
+        }
+        // The cup totals are already combined by the rule of each cup
+        Ok(ranking.rank(Scoring::Fibonacci.rule(), &result, &result_penalties))
+    }
+
+    async fn current_cup_score(&self, guild_id: i64, game: Game) -> Result<Vec<(Player, u32)>> {
+        let season = self.current_season(guild_id).await?;
+        self.cup_score(guild_id, game, season.id, false, None, Ranking::Sum)
+            .await
+    }
+
+    async fn current_hard_mode_cup_score(
+        &self,
+        guild_id: i64,
+        game: Game,
+    ) -> Result<Vec<(Player, u32)>> {
+        let season = self.current_season(guild_id).await?;
+        self.cup_score(guild_id, game, season.id, true, None, Ranking::Sum)
+            .await
+    }
+
+    // The winners are ranked the way the guild chose, and share the win unless the
+    // tie breakers of the guild separate them
+    pub async fn cup_winners(
+        &self,
+        guild_id: i64,
+        game: Game,
+        season_id: i64,
+    ) -> Result<Vec<Player>> {
+        let ranking = self.get_cup_ranking(guild_id).await?;
+        let leader_board = self
+            .cup_score(guild_id, game, season_id, false, None, ranking)
+            .await?;
+        if !is_tied(&leader_board) {
+            return Ok(winners(&leader_board, &HashMap::new(), &[]));
+        }
+        let rule = self
+            .resolve_scoring(guild_id, season_id, None)
+            .await?
+            .rule();
+        let config = self.get_config(guild_id).await?;
+        let results = self.cup_results(game, season_id, false).await?;
+        let fields = fields(&results);
+        let scored: Vec<Scored> = results
+            .iter()
+            .map(|(player_id, day, score, hard_mode)| {
+                let points = rule.points(game, *score, config.bonus(*hard_mode), &fields[day]);
+                (*player_id, game, *day, *score, points)
+            })
+            .collect();
+        let records = records(&scored, &self.get_posting_times(season_id).await?);
+        let tie_breakers = self.get_tie_breakers(guild_id).await?;
+        Ok(winners(&leader_board, &records, &tie_breakers))
+    }
+
+    // Normalised points of every player in the cup, across all games
+    pub async fn omnicup_score(
+        &self,
+        guild_id: i64,
+        season_id: i64,
+        scoring: Option<Scoring>,
+        ranking: Ranking,
+    ) -> Result<Vec<(Player, u32)>> {
+        let mut result: HashMap<i64, Vec<u32>> = HashMap::new();
+        let scored = self.omnicup_results(guild_id, season_id, scoring).await?;
+        for (player_id, _, _, _, points) in scored {
+            result.entry(player_id).or_default().push(points);
+        }
+        // The normalised points are always summed, whatever the rule
+        Ok(ranking.rank(Scoring::Fibonacci.rule(), &result, &HashMap::new()))
+    }
+
+    // Every result of the cup across all games, with its normalised points
+    async fn omnicup_results(
+        &self,
+        guild_id: i64,
+        season_id: i64,
+        scoring: Option<Scoring>,
+    ) -> Result<Vec<Scored>> {
+        let rule = self
+            .resolve_scoring(guild_id, season_id, scoring)
+            .await?
+            .rule();
+        let config = self.get_config(guild_id).await?;
+        let mut scored = vec![];
+        for game in Game::ALL {
+            let results = self.get_cup_results(game, season_id).await?;
+            let fields = fields(&results);
+            // The field strength of a day is the best result posted that day
+            let mut best: HashMap<i64, u32> = HashMap::new();
+            let points: Vec<(i64, i64, i64, u32)> = results
+                .iter()
+                .map(|(player_id, day, score, hard_mode)| {
+                    let points = rule.points(game, *score, config.bonus(*hard_mode), &fields[day]);
+                    let entry = best.entry(*day).or_default();
+                    *entry = (*entry).max(points);
+                    (*player_id, *day, *score, points)
+                })
+                .collect();
+            for (player_id, day, score, points) in points {
+                let points = omni_points(points, best[&day]);
+                scored.push((player_id, game, day, score, points));
+            }
+        }
+        Ok(scored)
+    }
+
+    async fn current_omnicup_score(&self, guild_id: i64) -> Result<Vec<(Player, u32)>> {
+        let season = self.current_season(guild_id).await?;
+        self.omnicup_score(guild_id, season.id, None, Ranking::Sum)
+            .await
+    }
+
+    pub async fn omnicup_winners(&self, guild_id: i64, season_id: i64) -> Result<Vec<Player>> {
+        let ranking = self.get_cup_ranking(guild_id).await?;
+        let leader_board = self
+            .omnicup_score(guild_id, season_id, None, ranking)
+            .await?;
+        if !is_tied(&leader_board) {
+            return Ok(winners(&leader_board, &HashMap::new(), &[]));
+        }
+        let scored = self.omnicup_results(guild_id, season_id, None).await?;
+        let records = records(&scored, &self.get_posting_times(season_id).await?);
+        let tie_breakers = self.get_tie_breakers(guild_id).await?;
+        Ok(winners(&leader_board, &records, &tie_breakers))
+    }
+
+    pub async fn current_cup_winners(&self, guild_id: i64, game: Game) -> Result<Vec<Player>> {
+        let season = self.current_season(guild_id).await?;
+        self.cup_winners(guild_id, game, season.id).await
+    }
+
+    // The cups of a season whose final standings are already stored
+    pub async fn get_closed_cups(&self, season_id: i64) -> Result<Vec<String>> {
+        let res = sqlx::query!("SELECT cup FROM closed_cup WHERE season_id = ?", season_id)
+            .fetch_all(&self.database)
+            .await?
+            .into_iter()
+            .map(|row| row.cup)
+            .collect();
+        Ok(res)
+    }
+
+    // Stores the final standings of a closed cup for the hall of fame, by game name or omni,
+    // and marks the cup closed even when nobody played it. False if it was already closed.
+    pub async fn new_cup_result(&self, guild_id: i64, season_id: i64, cup: &str) -> Result<bool> {
+        if self
+            .get_closed_cups(season_id)
+            .await?
+            .iter()
+            .any(|closed| closed == cup)
+        {
+            return Ok(false);
+        }
+        let ranking = self.get_cup_ranking(guild_id).await?;
+        let (game, leader_board, winners) = match cup {
+            "omni" => (
+                None,
+                self.omnicup_score(guild_id, season_id, None, ranking)
+                    .await?,
+                self.omnicup_winners(guild_id, season_id).await?,
+            ),
+            game => {
+                let game: Game = game.parse()?;
+                (
+                    Some(game),
+                    self.cup_score(guild_id, game, season_id, false, None, ranking)
+                        .await?,
+                    self.cup_winners(guild_id, game, season_id).await?,
+                )
+            }
+        };
+        let medals = self.get_cup_medals(season_id, game).await?;
+        let mut transaction = self.database.begin().await?;
+        for (player, placement, points) in standings(&leader_board, &winners) {
+            let player_id = player.id as i64;
+            let [gold, silver, bronze] = medals.get(&player_id).copied().unwrap_or_default();
+            sqlx::query!(
+                "INSERT INTO cup_result (season_id, cup, player_id, placement, points, gold, silver, bronze)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                season_id,
+                cup,
+                player_id,
+                placement,
+                points,
+                gold,
+                silver,
+                bronze
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        let timestamp = chrono::Utc::now().timestamp();
+        let closed = sqlx::query!(
+            "INSERT INTO closed_cup (season_id, cup, closed_at) VALUES (?, ?, ?) ON CONFLICT DO NOTHING",
+            season_id,
+            cup,
+            timestamp
+        )
+        .execute(&mut transaction)
+        .await?
+        .rows_affected();
+        transaction.commit().await?;
+        Ok(closed > 0)
+    }
+
+    // The daily medals every player won during a cup, in one game or in all of them
+    async fn get_cup_medals(
+        &self,
+        season_id: i64,
+        game: Option<Game>,
+    ) -> Result<HashMap<i64, [i64; 3]>> {
+        let game = game.map(|game| game.name());
+        let res = sqlx::query!(
+            r#"SELECT player_id,
+            COUNT(CASE WHEN score = gold THEN 1 END) AS "gold!: i64",
+            COUNT(CASE WHEN score = silver THEN 1 END) AS "silver!: i64",
+            COUNT(CASE WHEN score = bronze THEN 1 END) AS "bronze!: i64"
+            FROM score_sheet JOIN daily ON score_sheet.guild_id = daily.guild_id
+                AND score_sheet.game = daily.game AND score_sheet.day = daily.id
+            WHERE season_id = ? AND deleted_at IS NULL AND (? IS NULL OR score_sheet.game = ?)
+            GROUP BY player_id"#,
+            season_id,
+            game,
+            game
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| (row.player_id, [row.gold, row.silver, row.bronze]))
+        .collect();
+        Ok(res)
+    }
+
+    // The stored standings of every closed cup of a game or omni, oldest season first
+    pub async fn get_cup_history(&self, guild_id: i64, cup: &str) -> Result<Vec<CupResult>> {
+        let res = sqlx::query!(
+            "SELECT season.name, player_id, placement, points, gold, silver, bronze
+            FROM cup_result JOIN season ON season.id = cup_result.season_id
+            WHERE season.guild_id = ? AND cup = ? ORDER BY season.start_date, placement",
+            guild_id,
+            cup
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| CupResult {
+            season: row.name,
+            player: Player::from(row.player_id),
+            placement: row.placement as u32,
+            points: row.points as u32,
+            medals: [row.gold, row.silver, row.bronze],
+        })
+        .collect();
+        Ok(res)
+    }
+}
+
+// The final standing of a player in a closed cup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CupResult {
+    pub season: String,
+    pub player: Player,
+    pub placement: u32,
+    pub points: u32,
+    // Daily gold, silver and bronze medals won during the cup
+    pub medals: [i64; 3],
+}
+
+/// A unit of work on the database. What is written through it is committed all at once, or
+/// rolled back if it is dropped before that, e.g. when a statement fails.
+pub(crate) struct UnitOfWork {
+    transaction: sqlx::Transaction<'static, sqlx::Sqlite>,
+}
+
+impl UnitOfWork {
+    pub async fn commit(self) -> Result<()> {
+        self.transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_config(&mut self, guild_id: i64, config: &GuildConfig) -> Result<()> {
+        let (scoring, period) = (config.scoring.name(), config.period.value());
+        let (time_zone, language) = (config.time_zone.name(), config.language.value());
+        let (missed_day_penalty, hard_mode_bonus) =
+            (config.missed_day_penalty, config.hard_mode_bonus);
+        sqlx::query!(
+            "INSERT INTO guild_config (guild_id, tracked_channel_id, announcement_channel_id, scoring_rule, season_period, time_zone, language, reactions, admin_role_id, missed_day_penalty, grid_validation, hard_mode_bonus)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id) DO UPDATE SET (tracked_channel_id, announcement_channel_id, scoring_rule, season_period, time_zone, language, reactions, admin_role_id, missed_day_penalty, grid_validation, hard_mode_bonus)
+            = (excluded.tracked_channel_id, excluded.announcement_channel_id, excluded.scoring_rule, excluded.season_period, excluded.time_zone, excluded.language, excluded.reactions, excluded.admin_role_id, excluded.missed_day_penalty, excluded.grid_validation, excluded.hard_mode_bonus)",
+            guild_id,
+            config.tracked_channel,
+            config.announcement_channel,
+            scoring,
+            period,
+            time_zone,
+            language,
+            config.reactions,
+            config.admin_role,
+            missed_day_penalty,
+            config.grid_validation,
+            hard_mode_bonus
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn new_audit_entry(
+        &mut self,
+        guild_id: i64,
+        actor_id: Option<i64>,
+        action: &str,
+        msg_id: Option<i64>,
+        detail: &str,
+    ) -> Result<()> {
+        insert_audit_entry(
+            &mut self.transaction,
+            guild_id,
+            actor_id,
+            action,
+            msg_id,
+            detail,
+        )
+        .await
+    }
+
+    pub async fn new_player(&mut self, player_id: i64) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO player (id) VALUES (?) ON CONFLICT DO NOTHING",
+            player_id
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn new_daily(&mut self, guild_id: i64, game: Game, day: i64) -> Result<()> {
+        let game = game.name();
+        sqlx::query!(
+            "INSERT INTO daily (guild_id, id, game) VALUES (?, ?, ?) ON CONFLICT DO NOTHING",
+            guild_id,
+            day,
+            game
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(())
+    }
+
+    // Whether the score sheet was stored, a player only has one result a day
+    pub async fn new_score_sheet(
+        &mut self,
+        msg_id: i64,
+        guild_id: i64,
+        channel_id: i64,
+        player_id: i64,
+        season_id: i64,
+        result: &GameResult,
+    ) -> Result<bool> {
+        let grid = result.grid();
+        let game = result.game.name();
+        // Conflict = Cheater, unless the earlier result was deleted
+        let stored = sqlx::query!(
+            "INSERT INTO score_sheet (msg_id, guild_id, channel_id, day, player_id, score, season_id, grid, hard_mode, game) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id, player_id, game, day) DO UPDATE SET (msg_id, channel_id, score, season_id, grid, hard_mode, deleted_at) = (excluded.msg_id, excluded.channel_id, excluded.score, excluded.season_id, excluded.grid, excluded.hard_mode, NULL)
+            WHERE score_sheet.deleted_at IS NOT NULL",
+            msg_id,
+            guild_id,
+            channel_id,
+            result.day,
+            player_id,
+            result.score,
+            season_id,
+            grid,
+            result.hard_mode,
+            game,
+        )
+        .execute(&mut self.transaction)
+        .await?
+        .rows_affected();
+        Ok(stored > 0)
+    }
+
+    // Replaces the result of a counted score sheet. A deleted result of the player on the new
+    // day gives way, as it would to a new result.
+    pub async fn update_score_sheet(&mut self, msg_id: i64, result: &GameResult) -> Result<()> {
+        let grid = result.grid();
+        let game = result.game.name();
+        sqlx::query!(
+            "DELETE FROM score_sheet WHERE game = ? AND day = ? AND deleted_at IS NOT NULL
+            AND EXISTS (SELECT 1 FROM score_sheet AS edited WHERE edited.msg_id = ?
+                AND edited.guild_id = score_sheet.guild_id AND edited.player_id = score_sheet.player_id)",
+            game,
+            result.day,
+            msg_id
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        sqlx::query!(
+            "UPDATE score_sheet SET (game, day, score, grid, hard_mode) = (?, ?, ?, ?, ?) WHERE msg_id = ?",
+            game,
+            result.day,
+            result.score,
+            grid,
+            result.hard_mode,
+            msg_id
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(())
+    }
+
+    // Retracts a score sheet without removing it, returns it if there was one
+    pub async fn delete_score_sheet(&mut self, msg_id: i64) -> Result<Option<Scoresheet>> {
+        let Some(score_sheet) = select_score_sheet(&mut self.transaction, msg_id).await? else {
+            return Ok(None);
+        };
+        let deleted_at = chrono::Utc::now().timestamp();
+        sqlx::query!(
+            "UPDATE score_sheet SET deleted_at = ? WHERE msg_id = ?",
+            deleted_at,
+            msg_id
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(Some(score_sheet))
+    }
+
+    // Retracts a score sheet and recounts the placements of its day, returns it if there was one
+    pub async fn retract_score_sheet(&mut self, msg_id: i64) -> Result<Option<Scoresheet>> {
+        let Some(sheet) = self.delete_score_sheet(msg_id).await? else {
+            return Ok(None);
+        };
+        self.recompute_daily(sheet.guild_id, sheet.game(), sheet.day)
+            .await?;
+        Ok(Some(sheet))
+    }
+
+    pub async fn recompute_daily(&mut self, guild_id: i64, game: Game, day: i64) -> Result<()> {
+        let [gold, silver, bronze] =
+            select_derived_high_scores(&mut self.transaction, guild_id, game, day).await?;
+        self.update_daily(guild_id, game, day, gold, silver, bronze)
+            .await
+    }
+
+    async fn update_daily(
+        &mut self,
+        guild_id: i64,
+        game: Game,
+        day: i64,
+        gold: Option<i64>,
+        silver: Option<i64>,
+        bronze: Option<i64>,
+    ) -> Result<()> {
+        let game = game.name();
+        sqlx::query!(
+            "UPDATE daily SET (gold, silver, bronze) = (?, ?, ?) WHERE guild_id = ? AND game = ? AND id = ?",
+            gold,
+            silver,
+            bronze,
+            guild_id,
+            game,
+            day
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        Ok(())
+    }
+
+    // The season of the guild on the date. Dates outside of every season start a new one of
+    // the configured period, cut short where it would overlap the seasons around it.
+    pub async fn season_for_date(
+        &mut self,
+        guild_id: i64,
+        date: NaiveDate,
+        period: Period,
+    ) -> Result<Season> {
+        if let Some(season) = select_season_on(&mut self.transaction, guild_id, date).await? {
+            return Ok(season);
+        }
+        let day = date.to_string();
+
+        let (mut start, mut end) = period.window(date);
+        let mut name = period.name(start);
+        let previous = sqlx::query!(
+            r#"SELECT max(end_date) AS "end_date: String" FROM season WHERE guild_id = ? AND end_date < ?"#,
+            guild_id,
+            day
+        )
+        .fetch_one(&mut self.transaction)
+        .await?;
+        if let Some(previous_end) = previous.end_date {
+            start = start.max(previous_end.parse::<NaiveDate>()?.succ_opt().unwrap());
+        }
+        let next = sqlx::query!(
+            r#"SELECT min(start_date) AS "start_date: String" FROM season WHERE guild_id = ? AND start_date > ?"#,
+            guild_id,
+            day
+        )
+        .fetch_one(&mut self.transaction)
+        .await?;
+        if let Some(next_start) = next.start_date {
+            end = end.min(next_start.parse::<NaiveDate>()?.pred_opt().unwrap());
+        }
+        if (start, end) != period.window(date)
+            || select_season_by_name(&mut self.transaction, guild_id, &name)
+                .await?
+                .is_some()
+        {
+            name = format!("{start} – {end}");
+        }
+
+        debug!("Creating season {name} of guild {guild_id}");
+        let (start_date, end_date) = (start.to_string(), end.to_string());
+        // Another result of the same day may have created it already
+        sqlx::query!(
+            "INSERT INTO season (guild_id, name, start_date, end_date) VALUES (?, ?, ?, ?) ON CONFLICT(guild_id, name) DO NOTHING",
+            guild_id,
+            name,
+            start_date,
+            end_date
+        )
+        .execute(&mut self.transaction)
+        .await?;
+        select_season_by_name(&mut self.transaction, guild_id, &name)
+            .await?
+            .ok_or_else(|| anyhow!("Season {name} was not created"))
+    }
+}
+
+// The queries below are run on the pool as well as in a unit of work
+
+async fn select_score_sheet(
+    connection: &mut sqlx::SqliteConnection,
+    msg_id: i64,
+) -> Result<Option<Scoresheet>> {
+    let score_sheet = sqlx::query_as!(
+        Scoresheet,
+        "SELECT * FROM score_sheet WHERE msg_id = ? AND deleted_at IS NULL",
+        msg_id
+    )
+    .fetch_optional(connection)
+    .await?;
+    Ok(score_sheet)
+}
+
+// The three best distinct scores of a day, derived straight from its remaining score sheets
+async fn select_derived_high_scores(
+    connection: &mut sqlx::SqliteConnection,
+    guild_id: i64,
+    game: Game,
+    day: i64,
+) -> Result<[Option<i64>; 3]> {
+    let game = game.name();
+    let row = sqlx::query!(
+        r#"WITH ranked AS (
+            SELECT score, DENSE_RANK() OVER (ORDER BY score) AS placement
+            FROM score_sheet WHERE guild_id = ? AND game = ? AND day = ? AND score > 0 AND deleted_at IS NULL
+        )
+        SELECT
+            MAX(CASE WHEN placement = 1 THEN score END) AS "gold?: i64",
+            MAX(CASE WHEN placement = 2 THEN score END) AS "silver?: i64",
+            MAX(CASE WHEN placement = 3 THEN score END) AS "bronze?: i64"
+        FROM ranked"#,
+        guild_id,
+        game,
+        day
+    )
+    .fetch_one(connection)
+    .await?;
+    Ok([row.gold, row.silver, row.bronze])
+}
+
+async fn select_season_by_name(
+    connection: &mut sqlx::SqliteConnection,
+    guild_id: i64,
+    name: &str,
+) -> Result<Option<Season>> {
+    let name = Season::normalise_name(name);
+    let row = sqlx::query!(
+        r#"SELECT id AS "id!", name, start_date, end_date FROM season WHERE guild_id = ? AND name = ?"#,
+        guild_id,
+        name
+    )
+    .fetch_optional(connection)
+    .await?;
+    row.map(|row| Season::from_columns(row.id, row.name, &row.start_date, &row.end_date))
+        .transpose()
+}
+
+// The season of the guild the date falls in, if there is one yet
+async fn select_season_on(
+    connection: &mut sqlx::SqliteConnection,
+    guild_id: i64,
+    date: NaiveDate,
+) -> Result<Option<Season>> {
+    let day = date.to_string();
+    let row = sqlx::query!(
+        r#"SELECT id AS "id!", name, start_date, end_date FROM season WHERE guild_id = ? AND start_date <= ? AND end_date >= ?"#,
+        guild_id,
+        day,
+        day
+    )
+    .fetch_optional(connection)
+    .await?;
+    row.map(|row| Season::from_columns(row.id, row.name, &row.start_date, &row.end_date))
+        .transpose()
+}
+
+async fn insert_audit_entry(
+    connection: &mut sqlx::SqliteConnection,
+    guild_id: i64,
+    actor_id: Option<i64>,
+    action: &str,
+    msg_id: Option<i64>,
+    detail: &str,
+) -> Result<()> {
+    let timestamp = chrono::Utc::now().timestamp();
+    sqlx::query!(
+        "INSERT INTO audit_log (guild_id, timestamp, actor_id, action, msg_id, detail) VALUES (?, ?, ?, ?, ?, ?)",
+        guild_id,
+        timestamp,
+        actor_id,
+        action,
+        msg_id,
+        detail
+    )
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+// The (player_id, score, msg_id) of everyone who posted a puzzle
+type Match = Vec<(i64, i64, i64)>;
+
+// A result as (player_id, game, day, score, points)
+type Scored = (i64, Game, i64, i64, u32);
+
+fn is_tied(leader_board: &[(Player, u32)]) -> bool {
+    matches!(leader_board, [(_, first), (_, second), ..] if first == second)
+}
+
+// What every player did in a cup, a gold being the most points of a day
+fn records(
+    scored: &[Scored],
+    posting_times: &HashMap<(i64, Game, i64), i64>,
+) -> HashMap<i64, Record> {
+    let mut best: HashMap<(Game, i64), u32> = HashMap::new();
+    for (_, game, day, _, points) in scored {
+        let entry = best.entry((*game, *day)).or_default();
+        *entry = (*entry).max(*points);
+    }
+    let mut records: HashMap<i64, Record> = HashMap::new();
+    for (player_id, game, day, score, points) in scored {
+        let gold = *points > 0 && *points == best[&(*game, *day)];
+        let msg_id = posting_times.get(&(*player_id, *game, *day)).copied();
+        records
+            .entry(*player_id)
+            .or_default()
+            .add(*score, *points, gold, msg_id);
+    }
+    records
+}
+
+// All scores of each day, as (player_id, day, score, hard_mode) results
+fn fields(results: &[(i64, i64, i64, bool)]) -> HashMap<i64, Vec<i64>> {
+    let mut fields: HashMap<i64, Vec<i64>> = HashMap::new();
+    for (_, day, score, _) in results {
+        fields.entry(*day).or_default().push(*score);
+    }
+    fields
+}
+
+// Scores the results of one game in one cup, the points of every game per player
+fn calculate_points(
+    rule: &dyn ScoringRule,
+    game: Game,
+    results: &[(i64, i64, i64, bool)],
+    config: &GuildConfig,
+) -> HashMap<i64, Vec<u32>> {
+    debug!("Calculating points of {} results", results.len());
+    let fields = fields(results);
+    let mut points: HashMap<i64, Vec<u32>> = HashMap::new();
+    for (player_id, day, score, hard_mode) in results {
+        let bonus = config.bonus(*hard_mode);
+        let result_points = rule.points(game, *score, bonus, &fields[day]);
+        points.entry(*player_id).or_default().push(result_points);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::score::{points, OMNI_POINTS},
+        config::Language,
+    };
+    use chrono_tz::Tz;
+
+    const GUILD: i64 = 1;
+    const CHANNEL: i64 = 10;
+
+    async fn test_database(name: &str) -> Database {
+        let path =
+            std::env::temp_dir().join(format!("wordle_bot_{name}_{}.sqlite", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        Database::new(path.to_str().unwrap()).await.unwrap()
+    }
+
+    // The season of a month in 2023
+    async fn season(database: &Database, month: u32) -> i64 {
+        let date = NaiveDate::from_ymd_opt(2023, month, 1).unwrap();
+        database.season_for_date(GUILD, date).await.unwrap().id
+    }
+
+    async fn add_score(database: &Database, player_id: i64, day: i64, score: i64) {
+        let result = GameResult {
+            game: Game::Wordle,
+            day,
+            score,
+            hard_mode: false,
+            guesses: vec![],
+        };
+        new_daily(database, GUILD, Game::Wordle, day).await;
+        let january = season(database, 1).await;
+        new_score_sheet(
+            database,
+            player_id * 1000 + day,
+            GUILD,
+            player_id,
+            january,
+            &result,
+        )
+        .await;
+    }
+
+    // Stores a score sheet in the given season, whenever it was posted
+    async fn new_score_sheet(
+        database: &Database,
+        msg_id: i64,
+        guild_id: i64,
+        player_id: i64,
+        season_id: i64,
+        result: &GameResult,
+    ) -> bool {
+        let mut work = database.begin().await.unwrap();
+        work.new_player(player_id).await.unwrap();
+        let stored = work
+            .new_score_sheet(msg_id, guild_id, CHANNEL, player_id, season_id, result)
+            .await
+            .unwrap();
+        work.commit().await.unwrap();
+        stored
+    }
+
+    async fn new_daily(database: &Database, guild_id: i64, game: Game, day: i64) {
+        let mut work = database.begin().await.unwrap();
+        work.new_daily(guild_id, game, day).await.unwrap();
+        work.commit().await.unwrap();
+    }
+
+    async fn recompute_daily(database: &Database, game: Game, day: i64) {
+        let mut work = database.begin().await.unwrap();
+        work.recompute_daily(GUILD, game, day).await.unwrap();
+        work.commit().await.unwrap();
+    }
+
+    async fn retract_score_sheet(database: &Database, msg_id: i64) -> Option<Scoresheet> {
+        let mut work = database.begin().await.unwrap();
+        let sheet = work.retract_score_sheet(msg_id).await.unwrap();
+        work.commit().await.unwrap();
+        sheet
+    }
+
+    async fn set_config(database: &Database, config: &GuildConfig) {
+        let mut work = database.begin().await.unwrap();
+        work.set_config(GUILD, config).await.unwrap();
+        work.commit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_derived_high_scores() {
+        let database = test_database("derived_high_scores").await;
+        assert_eq!(
+            database
+                .derived_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [None; 3]
+        );
+
+        // Ties share a placement and failures never get a medal
+        for (player, score) in [(1, 4), (2, 4), (3, 0)] {
+            add_score(&database, player, 1, score).await;
+        }
+        assert_eq!(
+            database
+                .derived_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(4), None, None]
+        );
+
+        for (player, score) in [(4, 6), (5, 2), (6, 5), (7, 3)] {
+            add_score(&database, player, 1, score).await;
+        }
+        assert_eq!(
+            database
+                .derived_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(2), Some(3), Some(4)]
+        );
+
+        retract_score_sheet(&database, 5001).await;
+        assert_eq!(
+            database
+                .derived_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(3), Some(4), Some(5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_omnicup_score() {
+        let database = test_database("omnicup_score").await;
+        let january = season(&database, 1).await;
+        let february = season(&database, 2).await;
+        // Winning a day is worth the same in every game, however the game is scored
+        add_score(&database, 1, 1, 2).await;
+        add_score(&database, 2, 1, 4).await;
+        let connections = GameResult {
+            game: Game::Connections,
+            day: 1,
+            score: 4,
+            hard_mode: false,
+            guesses: vec![],
+        };
+        new_daily(&database, GUILD, Game::Connections, 1).await;
+        new_score_sheet(&database, 2001, GUILD, 2, january, &connections).await;
+        let score = database
+            .omnicup_score(GUILD, january, None, Ranking::Sum)
+            .await
+            .unwrap();
+        let wordle_second = omni_points(points(Game::Wordle, 4, 0), points(Game::Wordle, 2, 0));
+        assert_eq!(
+            score,
+            vec![
+                (Player::from(2), OMNI_POINTS + wordle_second),
+                (Player::from(1), OMNI_POINTS)
+            ]
+        );
+        assert_eq!(
+            database.omnicup_winners(GUILD, january).await.unwrap(),
+            vec![Player::from(2)]
+        );
+        assert!(database
+            .omnicup_winners(GUILD, february)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scoring_rules() {
+        let database = test_database("scoring_rules").await;
+        let january = season(&database, 1).await;
+        let february = season(&database, 2).await;
+        for (player, day, score) in [(1, 1, 2), (2, 1, 4), (3, 1, 0), (1, 2, 5), (2, 2, 3)] {
+            add_score(&database, player, day, score).await;
+        }
+        let score = |scoring| {
+            database.cup_score(GUILD, Game::Wordle, january, false, scoring, Ranking::Sum)
+        };
+        assert_eq!(
+            score(None).await.unwrap(),
+            vec![(Player::from(1), 8 + 2), (Player::from(2), 3 + 5)]
+        );
+        assert_eq!(
+            score(Some(Scoring::BeatTheField)).await.unwrap(),
+            vec![(Player::from(1), 2), (Player::from(2), 1 + 1)]
+        );
+        assert_eq!(
+            score(Some(Scoring::OfficialAverage)).await.unwrap(),
+            vec![(Player::from(1), 350), (Player::from(2), 350)]
+        );
+
+        // The rule of the cup is used unless another one is asked for
+        database
+            .set_scoring_rule(GUILD, january, Scoring::Linear)
+            .await
+            .unwrap();
+        assert_eq!(
+            score(None).await.unwrap(),
+            vec![(Player::from(1), 5 + 2), (Player::from(2), 3 + 4)]
+        );
+        assert_eq!(
+            database
+                .total(GUILD, Game::Wordle, None, Ranking::Sum)
+                .await
+                .unwrap(),
+            score(None).await.unwrap()
+        );
+        assert_eq!(
+            database.get_scoring_rule(GUILD, february).await.unwrap(),
+            Scoring::Fibonacci
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config() {
+        let database = test_database("config").await;
+        assert_eq!(
+            database.get_config(GUILD).await.unwrap(),
+            GuildConfig::default()
+        );
+        let config = GuildConfig {
+            tracked_channel: Some(CHANNEL),
+            scoring: Scoring::Linear,
+            period: Period::Weekly,
+            time_zone: Tz::America__Santiago,
+            language: Language::English,
+            reactions: false,
+            admin_role: Some(3),
+            missed_day_penalty: Some(2),
+            grid_validation: false,
+            hard_mode_bonus: 1,
+            ..GuildConfig::default()
+        };
+        set_config(&database, &config).await;
+        assert_eq!(database.get_config(GUILD).await.unwrap(), config);
+        assert_eq!(
+            database.get_config(2).await.unwrap(),
+            GuildConfig::default()
+        );
+
+        // New seasons are as long as configured, cups without a rule get the configured one
+        let date = NaiveDate::from_ymd_opt(2023, 11, 16).unwrap();
+        let week = database.season_for_date(GUILD, date).await.unwrap();
+        assert_eq!(week.name, "2023 v46");
+        assert_eq!(
+            database.get_scoring_rule(GUILD, week.id).await.unwrap(),
+            Scoring::Linear
+        );
+        let month = database.season_for_date(2, date).await.unwrap();
+        assert_eq!(month.name, "2023-11");
+        assert_eq!(
+            database.get_scoring_rule(2, month.id).await.unwrap(),
+            Scoring::Fibonacci
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cup_ranking() {
+        let database = test_database("cup_ranking").await;
+        let january = season(&database, 1).await;
+        // Player 1 plays every day, player 2 only once but better
+        for (player, day, score) in [(1, 1, 3), (1, 2, 3), (1, 3, 3), (2, 1, 2)] {
+            add_score(&database, player, day, score).await;
+        }
+        assert_eq!(
+            database
+                .cup_winners(GUILD, Game::Wordle, january)
+                .await
+                .unwrap(),
+            vec![Player::from(1)]
+        );
+        let average = Ranking::Average {
+            min_games: 0,
+            shrinkage: false,
+        };
+        database.set_cup_ranking(GUILD, average).await.unwrap();
+        assert_eq!(database.get_cup_ranking(GUILD).await.unwrap(), average);
+        assert_eq!(
+            database
+                .cup_winners(GUILD, Game::Wordle, january)
+                .await
+                .unwrap(),
+            vec![Player::from(2)]
+        );
+        // Too few games to be ranked at all
+        let threshold = Ranking::Average {
+            min_games: 2,
+            shrinkage: false,
+        };
+        database.set_cup_ranking(GUILD, threshold).await.unwrap();
+        assert_eq!(
+            database
+                .cup_score(GUILD, Game::Wordle, january, false, None, threshold)
+                .await
+                .unwrap(),
+            vec![(Player::from(1), 500)]
+        );
+        assert_eq!(
+            database
+                .cup_winners(GUILD, Game::Wordle, january)
+                .await
+                .unwrap(),
+            vec![Player::from(1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tie_breakers() {
+        let database = test_database("tie_breakers").await;
+        // Both get 10 points and a gold, player 1 reaches the total first
+        for (player, day, score) in [(1, 1, 2), (2, 1, 3), (1, 2, 5), (2, 2, 3)] {
+            add_score(&database, player, day, score).await;
+        }
+        let january = season(&database, 1).await;
+        let winners = || database.cup_winners(GUILD, Game::Wordle, january);
+        assert_eq!(
+            winners().await.unwrap(),
+            vec![Player::from(1), Player::from(2)]
+        );
+        database
+            .set_tie_breakers(GUILD, &[TieBreaker::Golds])
+            .await
+            .unwrap();
+        assert_eq!(
+            winners().await.unwrap(),
+            vec![Player::from(1), Player::from(2)]
+        );
+        let tie_breakers = [TieBreaker::Golds, TieBreaker::EarliestToTotal];
+        database
+            .set_tie_breakers(GUILD, &tie_breakers)
+            .await
+            .unwrap();
+        assert_eq!(
+            database.get_tie_breakers(GUILD).await.unwrap(),
+            tie_breakers
+        );
+        assert_eq!(winners().await.unwrap(), vec![Player::from(1)]);
+        // Normalised to the best result of each day the omnicup is no tie
+        assert_eq!(
+            database.omnicup_winners(GUILD, january).await.unwrap(),
+            vec![Player::from(2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missed_days() {
+        let database = test_database("missed_days").await;
+        let january = season(&database, 1).await;
+        let february = season(&database, 2).await;
+        // Player 2 joins on day 2 and skips day 3, day 5 is still being played
+        for (player, day, score) in [
+            (1, 1, 3),
+            (1, 2, 3),
+            (2, 2, 4),
+            (1, 3, 4),
+            (1, 4, 2),
+            (2, 4, 3),
+            (1, 5, 3),
+        ] {
+            add_score(&database, player, day, score).await;
+        }
+        let results = database
+            .get_cup_results(Game::Wordle, january)
+            .await
+            .unwrap();
+        assert_eq!(
+            database
+                .missed_days(GUILD, Game::Wordle, january, &results)
+                .await
+                .unwrap(),
+            HashMap::from([(2, 1)])
+        );
+        assert!(database
+            .missed_days(GUILD, Game::Wordle, february, &results)
+            .await
+            .unwrap()
+            .is_empty());
+        // Days with only retracted results were not played
+        retract_score_sheet(&database, 1003).await;
+        let results = database
+            .get_cup_results(Game::Wordle, january)
+            .await
+            .unwrap();
+        assert_eq!(
+            database
+                .missed_days(GUILD, Game::Wordle, january, &results)
+                .await
+                .unwrap(),
+            HashMap::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missed_day_penalty() {
+        let database = test_database("missed_day_penalty").await;
+        let january = season(&database, 1).await;
+        // Player 2 skips day 2, day 3 is still being played
+        for (player, day, score) in [(1, 1, 4), (2, 1, 3), (3, 1, 5), (1, 2, 4), (3, 2, 5)] {
+            add_score(&database, player, day, score).await;
+        }
+        add_score(&database, 1, 3, 2).await;
+        let score = |scoring| {
+            let database = &database;
+            async move {
+                database
+                    .cup_score(
+                        GUILD,
+                        Game::Wordle,
+                        january,
+                        false,
+                        Some(scoring),
+                        Ranking::Sum,
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(
+            score(Scoring::BeatTheField).await,
+            vec![(Player::from(1), 2), (Player::from(2), 2)]
+        );
+        let config = GuildConfig {
+            missed_day_penalty: Some(1),
+            ..GuildConfig::default()
+        };
+        set_config(&database, &config).await;
+        // Only the skipping player loses, the others gain nothing from the skipped day
+        assert_eq!(
+            score(Scoring::BeatTheField).await,
+            vec![(Player::from(1), 2), (Player::from(2), 1)]
+        );
+        assert_eq!(
+            score(Scoring::Fibonacci).await,
+            vec![
+                (Player::from(1), 14),
+                (Player::from(2), 4),
+                (Player::from(3), 4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ratings() {
+        let database = test_database("ratings").await;
+        database.recompute_ratings(GUILD).await.unwrap();
+        assert!(database.get_ratings(GUILD).await.unwrap().is_empty());
+
+        for (player, day, score) in [(1, 1, 2), (2, 1, 4), (3, 1, 0), (1, 2, 3), (2, 2, 3)] {
+            add_score(&database, player, day, score).await;
+        }
+        database.recompute_ratings(GUILD).await.unwrap();
+        let ratings = database.get_ratings(GUILD).await.unwrap();
+        let players: Vec<Player> = ratings.iter().map(|(player, _)| *player).collect();
+        assert_eq!(
+            players,
+            vec![Player::from(1), Player::from(2), Player::from(3)]
+        );
+        assert!(ratings[0].1.rating > Rating::default().rating);
+        assert!(ratings[2].1.rating < Rating::default().rating);
+        assert_eq!(
+            database.get_player_rating(GUILD, 3).await.unwrap(),
+            Some(ratings[2].1)
+        );
+        assert_eq!(database.get_player_rating(GUILD, 4).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_inconsistent_dailies() {
+        let database = test_database("inconsistent_dailies").await;
+        add_score(&database, 1, 1, 3).await;
+        add_score(&database, 1, 2, 4).await;
+        recompute_daily(&database, Game::Wordle, 1).await;
+        assert_eq!(
+            database.inconsistent_dailies(GUILD).await.unwrap(),
+            vec![(Game::Wordle, 2, [None; 3], [Some(4), None, None])]
+        );
+        assert_eq!(database.recompute_all_dailies(GUILD).await.unwrap(), 2);
+        assert!(database
+            .inconsistent_dailies(GUILD)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_announcements() {
+        let database = test_database("announcements").await;
+        add_score(&database, 1, 1, 3).await;
+        let january = season(&database, 1).await;
+        assert!(database
+            .get_announced_cups(january)
+            .await
+            .unwrap()
+            .is_empty());
+        let winners = [Player::from(1)];
+        assert!(database
+            .new_announcement(january, "wordle", &winners)
+            .await
+            .unwrap());
+        assert!(!database
+            .new_announcement(january, "wordle", &winners)
+            .await
+            .unwrap());
+        assert!(database
+            .new_announcement(january, "omni", &[])
+            .await
+            .unwrap());
+        let mut announced = database.get_announced_cups(january).await.unwrap();
+        announced.sort();
+        assert_eq!(announced, vec!["omni", "wordle"]);
+    }
+
+    #[tokio::test]
+    async fn test_cup_results() {
+        let database = test_database("cup_results").await;
+        for (player, day, score) in [(1, 1, 2), (2, 1, 3), (1, 2, 4), (2, 2, 3)] {
+            add_score(&database, player, day, score).await;
+        }
+        database.recompute_all_dailies(GUILD).await.unwrap();
+        let january = season(&database, 1).await;
+        assert!(database
+            .new_cup_result(GUILD, january, "wordle")
+            .await
+            .unwrap());
+        assert!(!database
+            .new_cup_result(GUILD, january, "wordle")
+            .await
+            .unwrap());
+        let result = |player_id, placement, points| CupResult {
+            season: String::from("2023-01"),
+            player: Player::from(player_id),
+            placement,
+            points,
+            medals: [1, 1, 0],
+        };
+        assert_eq!(
+            database.get_cup_history(GUILD, "wordle").await.unwrap(),
+            vec![result(1, 1, 11), result(2, 2, 10)]
+        );
+        assert!(database
+            .get_cup_history(GUILD, "omni")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(database
+            .new_cup_result(GUILD, january, "omni")
+            .await
+            .unwrap());
+        assert_eq!(
+            database.get_cup_history(GUILD, "omni").await.unwrap().len(),
+            2
+        );
+        // A cup nobody played is closed once, without standings
+        assert!(database
+            .new_cup_result(GUILD, january, "nerdle")
+            .await
+            .unwrap());
+        assert!(!database
+            .new_cup_result(GUILD, january, "nerdle")
+            .await
+            .unwrap());
+        assert!(database
+            .get_cup_history(GUILD, "nerdle")
+            .await
+            .unwrap()
+            .is_empty());
+        let mut closed = database.get_closed_cups(january).await.unwrap();
+        closed.sort();
+        assert_eq!(closed, vec!["nerdle", "omni", "wordle"]);
+    }
+
+    #[tokio::test]
+    async fn test_guilds() {
+        let database = test_database("guilds").await;
+        add_score(&database, 1, 1, 3).await;
+        // The same player plays the same day in another guild, with a season of its own
+        let other = 2;
+        let date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let season = database.season_for_date(other, date).await.unwrap();
+        assert_ne!(season.id, self::season(&database, 1).await);
+        let result = GameResult {
+            game: Game::Wordle,
+            day: 1,
+            score: 2,
+            hard_mode: false,
+            guesses: vec![],
+        };
+        new_daily(&database, other, Game::Wordle, 1).await;
+        new_score_sheet(&database, 5000, other, 1, season.id, &result).await;
+        database.recompute_all_dailies(GUILD).await.unwrap();
+        database.recompute_all_dailies(other).await.unwrap();
+        assert_eq!(
+            database
+                .derived_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(3), None, None]
+        );
+        assert_eq!(
+            database
+                .derived_high_scores(other, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(2), None, None]
+        );
+        let mut guilds = database.get_guilds().await.unwrap();
+        guilds.sort();
+        assert_eq!(guilds, vec![GUILD, other]);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_marks() {
+        let database = test_database("backfill_marks").await;
+        let tracked = 20;
+        let config = GuildConfig {
+            tracked_channel: Some(tracked),
+            ..GuildConfig::default()
+        };
+        set_config(&database, &config).await;
+        assert_eq!(
+            database.get_backfill_channels().await.unwrap(),
+            vec![(GUILD, tracked, 0)]
+        );
+        // Channels with results continue after the newest one until they are read
+        add_score(&database, 1, 3, 4).await;
+        add_score(&database, 1, 2, 4).await;
+        assert_eq!(
+            database.get_backfill_mark(CHANNEL).await.unwrap(),
+            Some(1003)
+        );
+        assert_eq!(database.get_backfill_mark(tracked).await.unwrap(), None);
+        database
+            .set_backfill_mark(GUILD, CHANNEL, 5000)
+            .await
+            .unwrap();
+        database
+            .set_backfill_mark(GUILD, CHANNEL, 4000)
+            .await
+            .unwrap();
+        assert_eq!(
+            database.get_backfill_mark(CHANNEL).await.unwrap(),
+            Some(5000)
+        );
+        let mut channels = database.get_backfill_channels().await.unwrap();
+        channels.sort();
+        assert_eq!(channels, vec![(GUILD, CHANNEL, 5000), (GUILD, tracked, 0)]);
+        // Retracted results are not counted again
+        retract_score_sheet(&database, 1002).await;
+        assert!(database.is_counted(1002).await.unwrap());
+        assert!(!database.is_counted(1004).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retract_score_sheet() {
+        let database = test_database("retract_score_sheet").await;
+        add_score(&database, 1, 1, 2).await;
+        add_score(&database, 2, 1, 4).await;
+        recompute_daily(&database, Game::Wordle, 1).await;
+        // An edit that no longer passes validation voids the result it replaced
+        let sheet = retract_score_sheet(&database, 1001).await.unwrap();
+        assert_eq!((sheet.player_id, sheet.score), (1, 2));
+        assert!(database.get_score_sheet(1001).await.unwrap().is_none());
+        assert_eq!(
+            database
+                .get_daily_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(4), None, None]
+        );
+        assert!(retract_score_sheet(&database, 1001).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_score_sheet() {
+        let database = test_database("update_score_sheet").await;
+        add_score(&database, 1, 1, 3).await;
+        add_score(&database, 1, 2, 4).await;
+        add_score(&database, 1, 3, 5).await;
+        retract_score_sheet(&database, 1002).await;
+        async fn found(database: &Database, day: i64) -> Option<i64> {
+            database
+                .get_player_score_sheet(GUILD, 1, Game::Wordle, day)
+                .await
+                .unwrap()
+                .map(|sheet| sheet.msg_id)
+        }
+        assert_eq!(found(&database, 2).await, None);
+        assert_eq!(found(&database, 3).await, Some(1003));
+        // An edit onto a day with a deleted result takes its place
+        let result = GameResult {
+            game: Game::Wordle,
+            day: 2,
+            score: 2,
+            hard_mode: false,
+            guesses: vec![],
+        };
+        let mut work = database.begin().await.unwrap();
+        work.update_score_sheet(1001, &result).await.unwrap();
+        work.commit().await.unwrap();
+        assert_eq!(found(&database, 1).await, None);
+        assert_eq!(found(&database, 2).await, Some(1001));
+        // A day with a counted result can not be edited onto
+        let result = GameResult { day: 3, ..result };
+        let mut work = database.begin().await.unwrap();
+        assert!(work.update_score_sheet(1001, &result).await.is_err());
+    }
+
+    // Makes every statement of the kind on the table fail, until the trigger is dropped
+    async fn inject_failure(database: &Database, event: &str, table: &str) {
+        sqlx::query(&format!(
+            "CREATE TRIGGER fail BEFORE {event} ON {table} BEGIN SELECT RAISE(ABORT, 'injected failure'); END"
+        ))
+        .execute(&database.database)
+        .await
+        .unwrap();
+    }
+
+    async fn remove_failure(database: &Database) {
+        sqlx::query("DROP TRIGGER fail")
+            .execute(&database.database)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ingest() {
+        let database = test_database("ingest").await;
+        let result = |score| GameResult {
+            game: Game::Wordle,
+            day: 1,
+            score,
+            hard_mode: false,
+            guesses: vec![],
+        };
+        // 2023-01-01 12:00 UTC
+        let posted_at = 1672574400;
+        let ingest = |msg_id, player_id, score| {
+            let database = &database;
+            async move {
+                database
+                    .ingest(msg_id, GUILD, CHANNEL, player_id, posted_at, &result(score))
+                    .await
+            }
+        };
+        // Nothing is left behind when the score sheet can not be stored
+        inject_failure(&database, "INSERT", "score_sheet").await;
+        assert!(ingest(1001, 1, 3).await.is_err());
+        remove_failure(&database).await;
+        assert!(database.get_days(GUILD).await.unwrap().is_empty());
+        assert!(database.get_seasons(GUILD).await.unwrap().is_empty());
+        // Nor when the placements of the day can not be recounted
+        inject_failure(&database, "UPDATE", "daily").await;
+        assert!(ingest(1001, 1, 3).await.is_err());
+        remove_failure(&database).await;
+        assert!(database.get_score_sheet(1001).await.unwrap().is_none());
+        assert!(database.get_days(GUILD).await.unwrap().is_empty());
+
+        assert!(ingest(1001, 1, 3).await.unwrap());
+        assert_eq!(
+            database.get_seasons(GUILD).await.unwrap()[0].name,
+            "2023-01"
+        );
+        assert_eq!(
+            database
+                .get_daily_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(3), None, None]
+        );
+        // A second result of the day is not stored, nor counted
+        assert!(!ingest(1002, 1, 2).await.unwrap());
+        assert!(database.get_score_sheet(1002).await.unwrap().is_none());
+        assert_eq!(
+            database
+                .get_daily_high_scores(GUILD, Game::Wordle, 1)
+                .await
+                .unwrap(),
+            [Some(3), None, None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unit_of_work_rollback() {
+        let database = test_database("unit_of_work_rollback").await;
+        let mut work = database.begin().await.unwrap();
+        work.new_daily(GUILD, Game::Wordle, 1).await.unwrap();
+        drop(work);
+        assert!(database.get_days(GUILD).await.unwrap().is_empty());
+        let mut work = database.begin().await.unwrap();
+        work.new_daily(GUILD, Game::Wordle, 1).await.unwrap();
+        work.commit().await.unwrap();
+        assert_eq!(
+            database.get_days(GUILD).await.unwrap(),
+            vec![(Game::Wordle, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_audit() {
+        let database = test_database("config_audit").await;
+        let config = GuildConfig {
+            reactions: false,
+            ..GuildConfig::default()
+        };
+        // A change that can not be audited is not made
+        inject_failure(&database, "INSERT", "audit_log").await;
+        let mut work = database.begin().await.unwrap();
+        work.set_config(GUILD, &config).await.unwrap();
+        assert!(work
+            .new_audit_entry(GUILD, Some(2), "config", None, "av")
+            .await
+            .is_err());
+        drop(work);
+        assert_eq!(
+            database.get_config(GUILD).await.unwrap(),
+            GuildConfig::default()
+        );
+        remove_failure(&database).await;
+        let mut work = database.begin().await.unwrap();
+        work.set_config(GUILD, &config).await.unwrap();
+        work.new_audit_entry(GUILD, Some(2), "config", None, "av")
+            .await
+            .unwrap();
+        work.commit().await.unwrap();
+        assert_eq!(database.get_config(GUILD).await.unwrap(), config);
+    }
+
+    #[tokio::test]
+    async fn test_merge_players() {
+        let database = test_database("merge_players").await;
+        // Both accounts played day 2, the result of the merged one is voided
+        for (player, day, score) in [(1, 1, 3), (1, 2, 4), (2, 2, 2), (2, 3, 5), (3, 1, 2)] {
+            add_score(&database, player, day, score).await;
+        }
+        assert_eq!(database.merge_players(GUILD, 1, 2).await.unwrap(), (1, 1));
+        assert!(database.get_score_sheet(1002).await.unwrap().is_none());
+        assert_eq!(
+            database
+                .get_score_sheet(1001)
+                .await
+                .unwrap()
+                .unwrap()
+                .player_id,
+            2
+        );
+        let january = season(&database, 1).await;
+        let results = database
+            .get_cup_results(Game::Wordle, january)
+            .await
+            .unwrap();
+        assert_eq!(results.iter().filter(|result| result.0 == 2).count(), 3);
+        assert!(results.iter().all(|result| result.0 != 1));
+        // Nothing is left to merge
+        assert_eq!(database.merge_players(GUILD, 1, 2).await.unwrap(), (0, 0));
+        // A deleted result does not void the merged one, which takes its place
+        add_score(&database, 4, 1, 3).await;
+        add_score(&database, 5, 1, 2).await;
+        retract_score_sheet(&database, 5001).await;
+        assert_eq!(database.merge_players(GUILD, 4, 5).await.unwrap(), (1, 0));
+        assert_eq!(
+            database
+                .get_score_sheet(4001)
+                .await
+                .unwrap()
+                .unwrap()
+                .player_id,
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seasons() {
+        let database = test_database("seasons").await;
+        let date = |m, d| NaiveDate::from_ymd_opt(2100, m, d).unwrap();
+        let march = database.season_for_date(GUILD, date(3, 10)).await.unwrap();
+        assert_eq!(
+            (march.name.as_str(), march.start, march.end),
+            ("2100-03", date(3, 1), date(3, 31))
+        );
+
+        // A custom season cuts short the season it starts in
+        let spring = database
+            .new_season(GUILD, "Vårcupen", date(3, 15), date(4, 10))
+            .await
+            .unwrap();
+        assert_eq!(
+            database.season_for_date(GUILD, date(3, 20)).await.unwrap(),
+            spring
+        );
+        let march = database
+            .get_season_by_name(GUILD, "2100/3")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(march.end, date(3, 14));
+        // The next season starts where the custom one ends
+        let april = database.season_for_date(GUILD, date(4, 20)).await.unwrap();
+        assert_eq!((april.start, april.end), (date(4, 11), date(4, 30)));
+        assert_eq!(april.name, "2100-04-11 – 2100-04-30");
+
+        assert!(database
+            .new_season(GUILD, "Påskcupen", date(3, 20), date(3, 25))
+            .await
+            .is_err());
+        let past = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        assert!(database
+            .new_season(GUILD, "Nyårscupen", past, past)
+            .await
+            .is_err());
     }
 }
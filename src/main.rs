@@ -1,16 +1,22 @@
 mod bot;
 mod command;
+mod config;
 mod database;
 mod parser;
 mod player;
+mod rating;
 mod scoresheet;
+mod scoring;
+mod season;
 mod utils;
+mod validation;
 
 use std::{fmt::Display, sync::Arc};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use bot::Bot;
-use chrono::Local;
+use chrono::Utc;
+use chrono_tz::Tz;
 use database::CachedDatabase as Database;
 use dotenv::dotenv;
 use log::{debug, error, info};
@@ -25,22 +31,31 @@ use serenity::{
     prelude::*,
 };
 
-use crate::utils::current_cup_number;
+use crate::{
+    config::Language,
+    parser::Game,
+    player::Player,
+    season::Season,
+    utils::{time_zone, until_midnight},
+};
 
-// TODO: Make env vars
-const CHANNEL_ID: u64 = 938727764037619712;
-const GUILD_ID: u64 = 486522741395161108;
 const CONGRATULATIONS: [&str; 10] = [
-        "@here Grattis {nick} till segern i wordlecupen, du är verkligen bäst!",
-        "@here Stort grattis till {nick} som vann wordlecupen, du är en riktig mästare!",
-        "@here Wow! Grattis {nick} till att ha erövrat wordlecupen, du är grym!",
-        "@here Fantastiskt jobbat, {nick}! Du är en vinnare och tar hem wordlecupen med bravur!",
-        "@here Grattis, {nick}! Du har lyckats bli mästaren i wordlecupen, en värdig vinnare!",
-        "@here Stort grattis till {nick} för att ha vunnit wordlecupen, du är en riktig mästare!",
-        "@here Fantastiskt jobbat, {nick}! Du har tagit hem segern i wordlecupen, du är grymt bra!",
-        "@here Grattis, {nick}! Ditt framstående spel har belönats med vinsten i wordlecupen, du är verkligen bäst!",
-        "@here Wow! {nick}, du är en riktig vinnare som har erövrat wordlecupen. Stort grattis!",
-        "@here Enorma gratulationer till {nick} för att ha segrat i wordlecupen. Du är en otroligt skicklig spelare!"
+        "@here Grattis {nick} till segern i {game}cupen, du är verkligen bäst!",
+        "@here Stort grattis till {nick} som vann {game}cupen, du är en riktig mästare!",
+        "@here Wow! Grattis {nick} till att ha erövrat {game}cupen, du är grym!",
+        "@here Fantastiskt jobbat, {nick}! Du är en vinnare och tar hem {game}cupen med bravur!",
+        "@here Grattis, {nick}! Du har lyckats bli mästaren i {game}cupen, en värdig vinnare!",
+        "@here Stort grattis till {nick} för att ha vunnit {game}cupen, du är en riktig mästare!",
+        "@here Fantastiskt jobbat, {nick}! Du har tagit hem segern i {game}cupen, du är grymt bra!",
+        "@here Grattis, {nick}! Ditt framstående spel har belönats med vinsten i {game}cupen, du är verkligen bäst!",
+        "@here Wow! {nick}, du är en riktig vinnare som har erövrat {game}cupen. Stort grattis!",
+        "@here Enorma gratulationer till {nick} för att ha segrat i {game}cupen. Du är en otroligt skicklig spelare!"
+    ];
+const CONGRATULATIONS_EN: [&str; 4] = [
+        "@here Congratulations {nick} on winning the {game} cup, you really are the best!",
+        "@here Well played, {nick}! The {game} cup is yours, a worthy champion!",
+        "@here Wow! {nick} has conquered the {game} cup. Huge congratulations!",
+        "@here Hats off to {nick}, the winner of the {game} cup. What a player!"
     ];
 
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
@@ -51,6 +66,18 @@ pub enum Placement {
     Loser,
 }
 
+impl Placement {
+    // The medal of a placement in a cup, counted from one
+    pub(crate) fn from_rank(rank: u32) -> Placement {
+        match rank {
+            1 => Placement::Gold,
+            2 => Placement::Silver,
+            3 => Placement::Bronze,
+            _ => Placement::Loser,
+        }
+    }
+}
+
 impl Display for Placement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
@@ -67,7 +94,14 @@ async fn update_channel_title(
     database: &Arc<RwLock<Database>>,
     ctx: &SerenityContext,
 ) -> Result<()> {
-    let dagens_ledare = match database.read().await.get_gold_medalist(None).await? {
+    let guild_id = channel.guild_id.0 as i64;
+    let language = database.read().await.get_config(guild_id).await?.language;
+    let dagens_ledare = match database
+        .read()
+        .await
+        .get_gold_medalist(guild_id, Game::Wordle, None)
+        .await?
+    {
         Some(players) => {
             let mut leaders: Vec<String> = Vec::default();
             for player in players {
@@ -77,29 +111,86 @@ async fn update_channel_title(
         }
         None => String::new(),
     };
-    let cup_ledare = match database.read().await.current_cup_leader().await? {
-        Some(player) => player.get_nick(channel.guild_id, ctx).await?,
-        None => String::new(),
+    let winners = database
+        .read()
+        .await
+        .current_cup_winners(guild_id, Game::Wordle)
+        .await?;
+    let mut cup_ledare: Vec<String> = Vec::default();
+    for player in winners {
+        cup_ledare.push(player.get_nick(channel.guild_id, ctx).await?);
+    }
+    let cup_ledare = cup_ledare.join(", ");
+    let title = match language {
+        Language::Swedish => format!("Dagens ledare: {dagens_ledare}\tCupledare: {cup_ledare}"),
+        Language::English => format!("Today's leader: {dagens_ledare}\tCup leader: {cup_ledare}"),
     };
-    let title = format!("Dagens ledare: {dagens_ledare}\tCupledare: {cup_ledare}");
     channel
         .edit(ctx, |c| c.topic(title))
         .await
         .map_err(|err| err.into())
 }
 
+// The leaders are shown in the tracked channel of the guild, or where the result was posted
+async fn refresh_channel_title(
+    channel_id: ChannelId,
+    database: &Arc<RwLock<Database>>,
+    ctx: &SerenityContext,
+) {
+    let Some(mut channel) = guild_channel(channel_id, ctx).await else {
+        return;
+    };
+    let guild_id = channel.guild_id.0 as i64;
+    let tracked = match database.read().await.get_config(guild_id).await {
+        Ok(config) => config.tracked_channel,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
+    };
+    if let Some(tracked) = tracked.filter(|tracked| *tracked != channel.id.0 as i64) {
+        let Some(tracked) = guild_channel(ChannelId(tracked as u64), ctx).await else {
+            return;
+        };
+        channel = tracked;
+    }
+    if let Err(e) = update_channel_title(&mut channel, database, ctx).await {
+        error!("{e}")
+    };
+}
+
+async fn guild_channel(channel_id: ChannelId, ctx: &SerenityContext) -> Option<GuildChannel> {
+    channel_id.to_channel(ctx).await.ok()?.guild()
+}
+
+// Reads what was missed in every channel results were counted in. A channel that cannot be
+// read does not keep the others from being read.
+async fn backfill_channels(ctx: &SerenityContext, database: Arc<RwLock<Database>>) -> Result<()> {
+    let channels = database.read().await.get_backfill_channels().await?;
+    let bot = Bot { database };
+    for (guild_id, channel_id, _) in channels {
+        let channel_id = ChannelId(channel_id as u64);
+        match bot
+            .backfill(GuildId(guild_id as u64), channel_id, false, ctx, None)
+            .await
+        {
+            Ok(backfill) => {
+                info!("Backfill of channel {channel_id} in guild {guild_id}: {backfill}");
+                if backfill.parsed > 0 {
+                    refresh_channel_title(channel_id, &bot.database, ctx).await;
+                }
+            }
+            Err(e) => error!("Backfill of channel {channel_id} in guild {guild_id} failed: {e}"),
+        }
+    }
+    Ok(())
+}
+
 #[async_trait]
 impl EventHandler for Bot {
     async fn ready(&self, ctx: SerenityContext, ready: Ready) {
         debug!("{} is connected!", ready.user.name);
 
-        let _guild_id = GuildId(
-            std::env::var("GUILD_ID")
-                .expect("Expected GUILD_ID in environment")
-                .parse()
-                .expect("GUILD_ID must be an integer"),
-        );
-
         // Commands can be global as well
         let commands = [
             Command::create_global_application_command(&ctx.http, |command| {
@@ -114,6 +205,22 @@ impl EventHandler for Bot {
                 command::stats::register(command)
             })
             .await,
+            Command::create_global_application_command(&ctx.http, |command| {
+                command::rating::register(command)
+            })
+            .await,
+            Command::create_global_application_command(&ctx.http, |command| {
+                command::hall::register(command)
+            })
+            .await,
+            Command::create_global_application_command(&ctx.http, |command| {
+                command::config::register(command)
+            })
+            .await,
+            Command::create_global_application_command(&ctx.http, |command| {
+                command::admin::register(command)
+            })
+            .await,
         ];
 
         debug!(
@@ -121,8 +228,13 @@ impl EventHandler for Bot {
             commands
         );
 
+        // Results posted while the bot was away are counted before any cup is closed, a cup
+        // that ended meanwhile would otherwise be won without them
         let database = self.database.clone();
         tokio::spawn(async move {
+            if let Err(e) = backfill_channels(&ctx, database.clone()).await {
+                error!("Backfill errored: {e}");
+            };
             if let Err(e) = check_for_cup_winner(ctx.clone(), database).await {
                 error!("Cup winner check loop errored: {e}");
             };
@@ -136,21 +248,40 @@ impl EventHandler for Bot {
     async fn interaction_create(&self, ctx: SerenityContext, interaction: Interaction) {
         if let Interaction::ApplicationCommand(command) = interaction {
             debug!("Received command interaction: {:#?}", command);
-
-            let content = match command.data.name.as_str() {
-                "ställning" => {
-                    command::score::run(&self.database, &ctx, &command.data.options).await
-                }
-                "dagens" => command::daily::run(&self.database, &ctx, &command.data.options).await,
-                "stats" => {
-                    command::stats::run(
-                        &command.user.id.into(),
-                        &self.database,
-                        &command.data.options,
-                    )
-                    .await
+            // Admin commands respond on their own, as they go
+            if command.data.name == "admin" {
+                if let Err(e) = command::admin::run(self, &ctx, &command).await {
+                    error!("{e}");
                 }
-                c => panic!("Unknown command recieved: {c}"),
+                return;
+            }
+
+            let options = &command.data.options;
+            let content = match command.guild_id {
+                // Every guild has cups of its own, there are none outside of one
+                None => Ok(String::from("Kommandona fungerar bara i en server.")),
+                Some(guild_id) => match command.data.name.as_str() {
+                    "ställning" => {
+                        command::score::run(&self.database, &ctx, guild_id, options).await
+                    }
+                    "dagens" => command::daily::run(&self.database, &ctx, guild_id, options).await,
+                    "rating" => command::rating::run(&self.database, &ctx, guild_id, options).await,
+                    "hall" => command::hall::run(&self.database, &ctx, guild_id, options).await,
+                    "config" => {
+                        let member = command.member.as_ref();
+                        command::config::run(&self.database, &ctx, guild_id, member, options).await
+                    }
+                    "stats" => {
+                        command::stats::run(
+                            &command.user.id.into(),
+                            guild_id,
+                            &self.database,
+                            options,
+                        )
+                        .await
+                    }
+                    c => panic!("Unknown command recieved: {c}"),
+                },
             }
             .unwrap();
 
@@ -168,84 +299,275 @@ impl EventHandler for Bot {
     }
 
     async fn message(&self, ctx: SerenityContext, msg: Message) {
-        if msg.content.starts_with("Wordle") {
+        // Results count in the guild they are posted in, direct messages in none
+        if msg.guild_id.is_none() {
+            return;
+        }
+        if parser::find_parser(&msg.content).is_some() {
             if let Err(e) = self.handle_wordle_message(&msg, &ctx).await {
                 error!("{e}");
             }
-            if let Ok(channel) = msg.channel(&ctx).await {
-                if let Some(mut guild_channel) = channel.guild() {
-                    if let Err(e) =
-                        update_channel_title(&mut guild_channel, &self.database, &ctx).await
-                    {
-                        error!("{e}")
-                    };
+            refresh_channel_title(msg.channel_id, &self.database, &ctx).await;
+        }
+    }
+
+    async fn message_update(
+        &self,
+        ctx: SerenityContext,
+        _old_if_available: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // Updates without content are e.g. link embeds being resolved. Results only count in
+        // a guild.
+        let Some(content) = event.content.as_ref() else {
+            return;
+        };
+        if event.guild_id.is_none() {
+            return;
+        }
+        // A message that no longer shows a result only matters if it was counted
+        if parser::find_parser(content).is_none() {
+            let counted = self
+                .database
+                .read()
+                .await
+                .get_score_sheet(event.id.0 as i64)
+                .await;
+            match counted {
+                Ok(Some(_)) => {}
+                Ok(None) => return,
+                Err(e) => {
+                    error!("{e}");
+                    return;
                 }
             }
-        // Admin messages - check for privilege, then execute and delete message
-        } else if msg.content == "!reset" {
-            if msg.author.name != "esjostrom" {
-                msg.channel_id
-                    .say(&ctx, "Du är ej betrodd med detta kommando")
-                    .await
-                    .unwrap();
-                return;
+        }
+        let mut msg = match new {
+            Some(msg) => msg,
+            None => match event.channel_id.message(&ctx, event.id).await {
+                Ok(msg) => msg,
+                Err(e) => {
+                    error!("{e}");
+                    return;
+                }
+            },
+        };
+        // Fetched messages do not know their guild
+        msg.guild_id = msg.guild_id.or(event.guild_id);
+        match self.handle_wordle_edit(&msg, &ctx).await {
+            Ok(true) => refresh_channel_title(msg.channel_id, &self.database, &ctx).await,
+            Ok(false) => {}
+            Err(e) => error!("{e}"),
+        }
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: SerenityContext,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        self.message_delete_bulk(ctx, channel_id, vec![deleted_message_id], None)
+            .await
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: SerenityContext,
+        _channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        // Only channels that lost a counted result show other leaders
+        match self
+            .handle_deletions(&multiple_deleted_messages_ids, &ctx)
+            .await
+        {
+            Ok(channels) => {
+                for channel_id in channels {
+                    refresh_channel_title(channel_id, &self.database, &ctx).await;
+                }
             }
-            self.read_old_messages(msg.channel_id, &ctx).await;
+            Err(e) => error!("{e}"),
         }
     }
 }
 
-// Actually 1 minute past midnight, just to be sure
-async fn wait_until_midnight() -> Result<()> {
-    let now = Local::now();
-    #[allow(deprecated)]
-    let tomorrow = now
-        .date()
-        .and_hms_opt(0, 1, 0)
-        .context("Could not set hms")?
-        .checked_add_days(chrono::Days::new(1))
-        .context("Could not add a day")?;
-    let duration = tomorrow.signed_duration_since(now);
+// Actually 1 minute past midnight, just to be sure. The first midnight of the time zones.
+async fn wait_until_midnight(time_zones: &[Tz]) -> Result<()> {
+    let now = Utc::now();
+    let duration = time_zones
+        .iter()
+        .map(|time_zone| until_midnight(now, *time_zone))
+        .min()
+        .unwrap_or_else(|| until_midnight(now, time_zone()));
     debug!("Waiting until midnight.");
     tokio::time::sleep(duration.to_std()?).await;
     debug!("Midnight reached.");
     Ok(())
 }
 
-// Checks if we have entered a new cup season, if so prints of the winner of the last cup.
+// Announces the winner of every cup that has ended, once. The first check catches up on
+// cups that ended while the bot was down.
 async fn check_for_cup_winner(
     ctx: serenity::client::Context,
     database: Arc<RwLock<Database>>,
 ) -> Result<()> {
     debug!("Cup winner check loop spawned.");
-    let mut current_cup = current_cup_number();
-    let channel_id: ChannelId = CHANNEL_ID.into();
-    let guild_id: GuildId = GUILD_ID.into();
     loop {
-        let cup = current_cup_number();
-        if current_cup != cup {
-            let message: String = match database.read().await.cup_leader(&current_cup).await? {
-                None => {
-                    error!("No leader in the current cup.");
-                    String::from("Ingen vinnare i denna cup.")
-                }
-                Some(player) => {
-                    let nick = player.get_nick(guild_id, &ctx.http).await?;
-                    CONGRATULATIONS
-                        .iter()
-                        .choose(&mut rand::thread_rng())
-                        .unwrap()
-                        .replace("{nick}", &nick)
+        let guilds = database.read().await.get_guilds().await?;
+        let mut time_zones = vec![];
+        // One guild failing, e.g. by removing the bot, does not keep the others waiting
+        for guild_id in guilds {
+            let config = database.read().await.get_config(guild_id).await;
+            match config {
+                Ok(config) => time_zones.push(config.time_zone),
+                Err(e) => {
+                    error!("Reading the config of guild {guild_id} failed: {e}");
+                    continue;
                 }
-            };
-            channel_id.say(&ctx, message).await?;
-            info!("Cup winner announced");
-            current_cup = cup;
+            }
+            if let Err(e) = announce_cup_winners(&ctx, &database, guild_id).await {
+                error!("Announcing the cups of guild {guild_id} failed: {e}");
+            }
+            // Replaying every day played is too slow to do for every result, the ratings
+            // take in the results of a day once it has ended
+            if let Err(e) = database.write().await.recompute_ratings(guild_id).await {
+                error!("Rating the players of guild {guild_id} failed: {e}");
+            }
         }
-        wait_until_midnight().await?
+        // Every guild has its cups checked at its own midnight
+        wait_until_midnight(&time_zones).await?
     }
 }
 
+// Announces every cup of the guild that has ended and not been announced yet
+async fn announce_cup_winners(
+    ctx: &serenity::client::Context,
+    database: &Arc<RwLock<Database>>,
+    guild_id: i64,
+) -> Result<()> {
+    let today = database.read().await.get_config(guild_id).await?.today();
+    let seasons = database.read().await.get_seasons(guild_id).await?;
+    for season in seasons.into_iter().filter(|season| season.end < today) {
+        let (closed, announced) = {
+            let database = database.read().await;
+            (
+                database.get_closed_cups(season.id).await?,
+                database.get_announced_cups(season.id).await?,
+            )
+        };
+        // Every game has its own cup, the omnicup sums the normalised points of all games
+        let cups = Game::ALL.iter().map(Game::name).chain(["omni"]);
+        // The final standings are kept for the hall of fame, of cups closed before too
+        for cup in cups
+            .clone()
+            .filter(|cup| !closed.iter().any(|closed| closed == cup))
+        {
+            let database = database.write().await;
+            if database.new_cup_result(guild_id, season.id, cup).await? {
+                info!("Stored the {cup} cup results of {}", season.name);
+            }
+        }
+        for cup in cups.filter(|cup| !announced.iter().any(|announced| announced == cup)) {
+            announce_cup(ctx, database, guild_id, &season, cup, false).await?;
+        }
+    }
+    Ok(())
+}
+
+// Announces the winners of a cup in the announcement channel of the guild. Guilds without
+// one use the tracked channel, or the channel they last posted a result in. Cups are
+// announced once, unless repeated on request. Returns whether anything was announced.
+pub(crate) async fn announce_cup(
+    ctx: &serenity::client::Context,
+    database: &Arc<RwLock<Database>>,
+    guild_id: i64,
+    season: &Season,
+    cup: &str,
+    repeat: bool,
+) -> Result<bool> {
+    let config = database.read().await.get_config(guild_id).await?;
+    let channel_id = match config.announcement_channel.or(config.tracked_channel) {
+        Some(channel_id) => Some(channel_id),
+        None => database.read().await.get_result_channel(guild_id).await?,
+    };
+    let Some(channel_id) = channel_id else {
+        return Ok(false);
+    };
+    let announced = database.read().await.get_announced_cups(season.id).await?;
+    if !repeat && announced.iter().any(|announced| announced == cup) {
+        return Ok(false);
+    }
+    let winners = {
+        let database = database.read().await;
+        match cup.parse::<Game>() {
+            Ok(game) => database.cup_winners(guild_id, game, season.id).await?,
+            Err(_) => database.omnicup_winners(guild_id, season.id).await?,
+        }
+    };
+    let message = match winners.is_empty() {
+        // Nobody played, nothing to announce
+        true if cup != Game::Wordle.name() => {
+            database
+                .write()
+                .await
+                .new_announcement(season.id, cup, &winners)
+                .await?;
+            return Ok(false);
+        }
+        true => {
+            error!("No leader in the {} cup.", season.name);
+            match config.language {
+                Language::Swedish => String::from("Ingen vinnare i denna cup."),
+                Language::English => String::from("No winner in this cup."),
+            }
+        }
+        false => congratulate(ctx, guild_id, config.language, &winners, cup).await?,
+    };
+    ChannelId(channel_id as u64).say(ctx, message).await?;
+    // Recorded once sent, an announcement that failed is tried again at the next check
+    database
+        .write()
+        .await
+        .new_announcement(season.id, cup, &winners)
+        .await?;
+    info!("{cup} cup winner of {} announced", season.name);
+    Ok(true)
+}
+
+// Shared wins congratulate every winner at once
+async fn congratulate(
+    ctx: &serenity::client::Context,
+    guild_id: i64,
+    language: Language,
+    winners: &[Player],
+    cup: &str,
+) -> Result<String> {
+    let mut nicks = vec![];
+    for player in winners {
+        let nick = player.get_nick(GuildId(guild_id as u64), &ctx.http).await?;
+        nicks.push(nick);
+    }
+    let (congratulations, and) = match language {
+        Language::Swedish => (&CONGRATULATIONS[..], "och"),
+        Language::English => (&CONGRATULATIONS_EN[..], "and"),
+    };
+    let nick = match nicks.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} {and} {last}", rest.join(", ")),
+        None => String::new(),
+    };
+    Ok(congratulations
+        .iter()
+        .choose(&mut rand::thread_rng())
+        .unwrap()
+        .replace("{nick}", &nick)
+        .replace("{game}", cup))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
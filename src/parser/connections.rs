@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{line_ending, multispace0, space0},
+    combinator,
+    multi::{count, separated_list1},
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, Game, GameParser, GameResult};
+
+// Four mistakes ends the game, every row that is not a single colour is a mistake
+const MAX_MISTAKES: usize = 4;
+
+// Solving all four groups without a mistake takes four guesses
+const SCORING_TABLE: [u32; 8] = [0, 0, 0, 0, 8, 5, 3, 2];
+
+pub(super) struct ConnectionsParser;
+
+impl GameParser for ConnectionsParser {
+    fn game(&self) -> Game {
+        Game::Connections
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Connections"
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &SCORING_TABLE
+    }
+
+    // Puzzle #1 was published 2023-06-12
+    fn epoch(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2023, 6, 11)
+    }
+}
+
+// Each colour is one of the groups, from easiest to trickiest
+fn parse_square(s: &str) -> IResult<&str, char> {
+    alt((
+        combinator::value('Y', tag("🟨")),
+        combinator::value('G', tag("🟩")),
+        combinator::value('B', tag("🟦")),
+        combinator::value('P', tag("🟪")),
+    ))(s)
+}
+
+fn parse_row(s: &str) -> IResult<&str, Vec<char>> {
+    terminated(count(parse_square, 4), space0)(s)
+}
+
+fn parse_header(s: &str) -> IResult<&str, i64> {
+    preceded(
+        tuple((tag("Connections"), multispace0, tag("Puzzle #"))),
+        parse_day,
+    )(s)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map_opt(
+        tuple((
+            parse_header,
+            preceded(
+                multispace0,
+                separated_list1(tuple((line_ending, space0)), parse_row),
+            ),
+        )),
+        |(day, rows)| {
+            let solved = rows
+                .iter()
+                .filter(|row| row.iter().all(|square| *square == row[0]))
+                .count();
+            let mistakes = rows.len() - solved;
+            if solved > 4 || mistakes > MAX_MISTAKES {
+                return None;
+            }
+            Some(GameResult {
+                game: Game::Connections,
+                day,
+                score: if solved == 4 { rows.len() as i64 } else { 0 },
+                hard_mode: false,
+                guesses: vec![],
+            })
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_connections() {
+        let corpus = [
+            ("Connections\nPuzzle #123\n🟨🟨🟨🟨\n🟩🟩🟩🟩\n🟦🟦🟦🟦\n🟪🟪🟪🟪", 123, 4),
+            ("Connections \nPuzzle #1,001\n🟨🟩🟨🟨\n🟨🟨🟨🟨\n🟩🟩🟩🟩\n🟦🟪🟦🟦\n🟦🟦🟦🟦\n🟪🟪🟪🟪", 1001, 6),
+            ("Connections\nPuzzle #123\n🟨🟩🟨🟨\n🟨🟨🟨🟨\n🟦🟪🟦🟦\n🟦🟪🟦🟦\n🟩🟩🟩🟩\n🟪🟦🟪🟪", 123, 0),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("Connections\nPuzzle #123\n🟨🟨🟨").is_err());
+    }
+}
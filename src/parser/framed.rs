@@ -0,0 +1,86 @@
+use anyhow::Result;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{multispace0, space0},
+    combinator,
+    multi::count,
+    sequence::{preceded, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, Game, GameParser, GameResult};
+use crate::command::score::FIB;
+
+pub(super) struct FramedParser;
+
+impl GameParser for FramedParser {
+    fn game(&self) -> Game {
+        Game::Framed
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Framed #"
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &FIB
+    }
+}
+
+// 🟥 is a wrong guess, 🟩 the right one and ⬛ frames never shown
+fn parse_frame(s: &str) -> IResult<&str, bool> {
+    preceded(
+        space0,
+        alt((
+            combinator::value(true, tag("🟩")),
+            combinator::value(false, alt((tag("🟥"), tag("⬛")))),
+        )),
+    )(s)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        tuple((
+            preceded(tag("Framed #"), parse_day),
+            preceded(tuple((multispace0, tag("🎥"))), count(parse_frame, 6)),
+        )),
+        |(day, frames)| GameResult {
+            game: Game::Framed,
+            day,
+            score: frames
+                .iter()
+                .position(|correct| *correct)
+                .map_or(0, |frame| frame as i64 + 1),
+            hard_mode: false,
+            guesses: vec![],
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_framed() {
+        let corpus = [
+            (
+                "Framed #123\n🎥 🟥 🟥 🟩 ⬛ ⬛ ⬛\n\nhttps://framed.wtf",
+                123,
+                3,
+            ),
+            ("Framed #456\n🎥 🟩 ⬛ ⬛ ⬛ ⬛ ⬛", 456, 1),
+            ("Framed #789\n🎥 🟥 🟥 🟥 🟥 🟥 🟥", 789, 0),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("Framed #123\n🎥 🟥 🟥").is_err());
+    }
+}
@@ -0,0 +1,324 @@
+mod connections;
+mod framed;
+mod nerdle;
+mod octordle;
+mod ordel;
+mod quordle;
+mod strands;
+mod wordle;
+mod worldle;
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    bytes::complete::take_while_m_n,
+    character::complete::{digit1, line_ending, multispace0, one_of, space0},
+    combinator,
+    multi::{many0, separated_list0},
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::validation::Rejection;
+
+/// The feedback colour of a single letter in a guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tile {
+    Correct,
+    Present,
+    Absent,
+}
+
+impl Tile {
+    /// Single character representation used when storing a grid in the database
+    pub(crate) fn as_char(&self) -> char {
+        match self {
+            Tile::Correct => 'G',
+            Tile::Present => 'Y',
+            Tile::Absent => '-',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Game {
+    Wordle,
+    Connections,
+    Strands,
+    Nerdle,
+    Quordle,
+    Octordle,
+    Worldle,
+    Framed,
+    Ordel,
+}
+
+impl Game {
+    pub(crate) const ALL: [Game; 9] = [
+        Game::Wordle,
+        Game::Connections,
+        Game::Strands,
+        Game::Nerdle,
+        Game::Quordle,
+        Game::Octordle,
+        Game::Worldle,
+        Game::Framed,
+        Game::Ordel,
+    ];
+
+    /// Identifier used in the database and as command option value
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Game::Wordle => "wordle",
+            Game::Connections => "connections",
+            Game::Strands => "strands",
+            Game::Nerdle => "nerdle",
+            Game::Quordle => "quordle",
+            Game::Octordle => "octordle",
+            Game::Worldle => "worldle",
+            Game::Framed => "framed",
+            Game::Ordel => "ordel",
+        }
+    }
+
+    pub(crate) fn parser(&self) -> &'static dyn GameParser {
+        match self {
+            Game::Wordle => &wordle::WordleParser,
+            Game::Connections => &connections::ConnectionsParser,
+            Game::Strands => &strands::StrandsParser,
+            Game::Nerdle => &nerdle::NerdleParser,
+            Game::Quordle => &quordle::QuordleParser,
+            Game::Octordle => &octordle::OctordleParser,
+            Game::Worldle => &worldle::WorldleParser,
+            Game::Framed => &framed::FramedParser,
+            Game::Ordel => &ordel::OrdelParser,
+        }
+    }
+}
+
+impl Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.name();
+        write!(f, "{}{}", name[..1].to_uppercase(), &name[1..])
+    }
+}
+
+impl FromStr for Game {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Game::ALL
+            .into_iter()
+            .find(|game| game.name() == s)
+            .ok_or_else(|| anyhow!("Unknown game {s}"))
+    }
+}
+
+/// A parsed share message of any game, normalised so that a lower score is better
+/// and a score of zero is a failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GameResult {
+    pub(crate) game: Game,
+    pub(crate) day: i64,
+    pub(crate) score: i64,
+    pub(crate) hard_mode: bool,
+    // Only games with a letter grid have guesses
+    pub(crate) guesses: Vec<Vec<Tile>>,
+}
+
+impl GameResult {
+    /// Encodes the guesses as one line of tile characters per guess
+    pub(crate) fn grid(&self) -> String {
+        self.guesses
+            .iter()
+            .map(|row| row.iter().map(Tile::as_char).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Parses the share messages of one of the daily games
+pub(crate) trait GameParser: Sync {
+    fn game(&self) -> Game;
+
+    /// Every share message of the game starts with this
+    fn prefix(&self) -> &'static str;
+
+    fn parse(&self, s: &str) -> Result<GameResult>;
+
+    /// Points indexed by score, scores past the end get the last entry
+    fn scoring_table(&self) -> &'static [u32];
+
+    /// The date puzzle zero would have been published, if the numbering follows the calendar
+    fn epoch(&self) -> Option<NaiveDate> {
+        None
+    }
+
+    /// Checks that the result is consistent with its grid
+    fn validate(&self, _result: &GameResult) -> Result<(), Rejection> {
+        Ok(())
+    }
+
+    fn describe_score(&self, score: i64) -> String {
+        match score {
+            0 => String::from("X"),
+            score => format!("{score} försök"),
+        }
+    }
+}
+
+/// Finds the game a message is a share message of
+pub(crate) fn find_parser(s: &str) -> Option<&'static dyn GameParser> {
+    Game::ALL
+        .iter()
+        .map(Game::parser)
+        .find(|parser| s.starts_with(parser.prefix()))
+}
+
+/// Parses a share message of any of the games
+pub(crate) fn parse_msg(s: &str) -> Result<GameResult> {
+    find_parser(s)
+        .ok_or_else(|| anyhow!("Not a share message"))?
+        .parse(s)
+}
+
+// Drops whatever follows the parsed result
+fn finish<T>(result: IResult<&str, T>) -> Result<T> {
+    match result.map(|(_, res)| res) {
+        Ok(n) => Ok(n),
+        Err(e) => Err(anyhow!(e.to_string())),
+    }
+}
+
+// Exactly three digits, as used after a thousands separator
+fn parse_digit_group(s: &str) -> IResult<&str, &str> {
+    terminated(
+        take_while_m_n(3, 3, |c: char| c.is_ascii_digit()),
+        combinator::not(digit1),
+    )(s)
+}
+
+// Parse number terminated by a space. Depending on locale the digits may be grouped
+// in thousands by a comma, a dot, a space or a (narrow) non-breaking space.
+fn parse_day(s: &str) -> IResult<&str, i64> {
+    combinator::map_res(
+        combinator::recognize(tuple((
+            digit1,
+            many0(tuple((one_of(",. \u{a0}\u{202f}"), parse_digit_group))),
+        ))),
+        |day: &str| {
+            day.chars()
+                .filter(char::is_ascii_digit)
+                .collect::<String>()
+                .parse::<i64>()
+        },
+    )(s)
+}
+
+fn parse_score(s: &str) -> IResult<&str, i64> {
+    combinator::map(
+        nom::character::complete::one_of("X123456"),
+        |score| match score {
+            'X' => 0, // Ok, since there's no 0 guess score
+            '1' => 1,
+            '2' => 2,
+            '3' => 3,
+            '4' => 4,
+            '5' => 5,
+            '6' => 6,
+            _ => unreachable!(),
+        },
+    )(s)
+}
+
+// Hard mode is marked by an asterisk directly after the score
+fn parse_hard_mode(s: &str) -> IResult<&str, bool> {
+    combinator::map(combinator::opt(tag("*")), |star| star.is_some())(s)
+}
+
+// Supports the dark (⬛), light (⬜) and high contrast (🟧🟦) themes
+fn parse_tile(s: &str) -> IResult<&str, Tile> {
+    alt((
+        combinator::value(Tile::Correct, alt((tag("🟩"), tag("🟧")))),
+        combinator::value(Tile::Present, alt((tag("🟨"), tag("🟦")))),
+        combinator::value(Tile::Absent, alt((tag("⬛"), tag("⬜")))),
+    ))(s)
+}
+
+// The grid follows the header after an empty line, one row per guess
+fn parse_grid<'a, F>(row: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<Tile>>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, Vec<Tile>>,
+{
+    preceded(
+        multispace0,
+        separated_list0(tuple((line_ending, space0)), row),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_day() {
+        assert_eq!(parse_day("547 bla"), Ok((" bla", 547)));
+        assert!(parse_day("blabla").is_err());
+        assert!(parse_day("").is_err());
+        assert_eq!(parse_day("1,234 4/6"), Ok((" 4/6", 1234)));
+        assert_eq!(parse_day("1.234 4/6"), Ok((" 4/6", 1234)));
+        assert_eq!(parse_day("1 234 4/6"), Ok((" 4/6", 1234)));
+        assert_eq!(parse_day("1\u{a0}234 4/6"), Ok((" 4/6", 1234)));
+        assert_eq!(parse_day("1\u{202f}234 4/6"), Ok((" 4/6", 1234)));
+        // A separator must be followed by a group of exactly three digits
+        assert_eq!(parse_day("1,23 4/6"), Ok((",23 4/6", 1)));
+        assert_eq!(parse_day("1,2345 4/6"), Ok((",2345 4/6", 1)));
+    }
+
+    #[test]
+    fn test_parse_score_char() {
+        for x in ['X', '1', '2', '3', '4', '5', '6'] {
+            assert!(parse_score(&format!("{x}/6")).is_ok());
+        }
+        assert!(parse_score("7/6").is_err())
+    }
+
+    #[test]
+    fn test_grid_encoding() {
+        let result = parse_msg("Wordle 547 2/6\n\n⬛🟨⬛⬛🟩\n🟩🟩🟩🟩🟩").unwrap();
+        assert_eq!(result.grid(), "-Y--G\nGGGGG");
+    }
+
+    #[test]
+    fn test_game_names() {
+        for game in Game::ALL {
+            assert_eq!(game.name().parse::<Game>().unwrap(), game);
+            assert_eq!(game.parser().game(), game);
+        }
+        assert_eq!(Game::Connections.to_string(), "Connections");
+        assert!("schack".parse::<Game>().is_err());
+    }
+
+    #[test]
+    fn test_find_parser() {
+        let corpus = [
+            ("Wordle 547 3/6", Game::Wordle),
+            ("Connections\nPuzzle #123", Game::Connections),
+            ("Strands #123", Game::Strands),
+            ("nerdlegame 728 3/6", Game::Nerdle),
+            ("Daily Quordle 123", Game::Quordle),
+            ("Daily Octordle #123", Game::Octordle),
+            ("#Worldle #123 4/6 (100%)", Game::Worldle),
+            ("Framed #123", Game::Framed),
+            ("Ordel 123 3/6", Game::Ordel),
+        ];
+        for (msg, game) in corpus {
+            assert_eq!(find_parser(msg).map(|parser| parser.game()), Some(game));
+        }
+        assert!(find_parser("Hej").is_none());
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space0,
+    combinator,
+    multi::count,
+    sequence::{preceded, separated_pair, terminated, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, parse_grid, parse_score, Game, GameParser, GameResult, Tile};
+use crate::{
+    command::score::FIB,
+    validation::{validate_grid, Rejection},
+};
+
+pub(super) struct NerdleParser;
+
+impl GameParser for NerdleParser {
+    fn game(&self) -> Game {
+        Game::Nerdle
+    }
+
+    fn prefix(&self) -> &'static str {
+        "nerdlegame "
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &FIB
+    }
+
+    fn validate(&self, result: &GameResult) -> Result<(), Rejection> {
+        validate_grid(result)
+    }
+}
+
+// Misplaced characters are purple in nerdle
+fn parse_tile(s: &str) -> IResult<&str, Tile> {
+    alt((
+        combinator::value(Tile::Correct, tag("🟩")),
+        combinator::value(Tile::Present, tag("🟪")),
+        combinator::value(Tile::Absent, alt((tag("⬛"), tag("⬜")))),
+    ))(s)
+}
+
+// An equation is eight characters long
+fn parse_row(s: &str) -> IResult<&str, Vec<Tile>> {
+    terminated(count(parse_tile, 8), space0)(s)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        tuple((
+            preceded(
+                tag("nerdlegame "),
+                separated_pair(parse_day, tag(" "), parse_score),
+            ),
+            tag("/6"),
+            parse_grid(parse_row),
+        )),
+        |((day, score), _, guesses)| GameResult {
+            game: Game::Nerdle,
+            day,
+            score,
+            hard_mode: false,
+            guesses,
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_nerdle() {
+        let msg = "nerdlegame 728 3/6\n\n🟪⬛🟪🟪🟩🟪⬛🟪\n🟪🟪🟩🟩🟩🟪🟩⬛\n🟩🟩🟩🟩🟩🟩🟩🟩";
+        let result = parse_msg(msg).unwrap();
+        assert_eq!(
+            (result.day, result.score, result.guesses.len()),
+            (728, 3, 3)
+        );
+        assert!(parse_msg("nerdlegame 728 3/6\n\n🟩🟩🟩🟩🟩")
+            .unwrap()
+            .guesses
+            .is_empty());
+        assert!(parse_msg("nerdlegame 728 7/6").is_err());
+    }
+}
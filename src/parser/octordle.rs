@@ -0,0 +1,97 @@
+use anyhow::Result;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::multispace0,
+    combinator,
+    multi::count,
+    sequence::{preceded, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, quordle, Game, GameParser, GameResult};
+
+// Eight words in thirteen guesses, the last word takes at least eight guesses
+const SCORING_TABLE: [u32; 14] = [0, 0, 0, 0, 0, 0, 0, 0, 13, 8, 5, 3, 2, 1];
+
+pub(super) struct OctordleParser;
+
+impl GameParser for OctordleParser {
+    fn game(&self) -> Game {
+        Game::Octordle
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Daily Octordle #"
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &SCORING_TABLE
+    }
+}
+
+// Past nine guesses octordle switches from keycaps to 🔟 and clock faces
+fn parse_word(s: &str) -> IResult<&str, Option<i64>> {
+    alt((
+        quordle::parse_keycap,
+        preceded(
+            multispace0,
+            alt((
+                combinator::value(Some(10), tag("🔟")),
+                combinator::value(Some(11), tag("🕚")),
+                combinator::value(Some(12), tag("🕛")),
+                combinator::value(Some(13), tag("🕐")),
+            )),
+        ),
+    ))(s)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        preceded(
+            tag("Daily Octordle #"),
+            tuple((parse_day, count(parse_word, 8))),
+        ),
+        |(day, words)| GameResult {
+            game: Game::Octordle,
+            day,
+            score: quordle::score(&words),
+            hard_mode: false,
+            guesses: vec![],
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_octordle() {
+        let corpus = [
+            (
+                "Daily Octordle #123\n8️⃣4️⃣\n5️⃣🔟\n🕛🕐\n6️⃣7️⃣\nScore: 71",
+                123,
+                13,
+            ),
+            (
+                "Daily Octordle #456\n8️⃣4️⃣\n5️⃣🔟\n9️⃣3️⃣\n6️⃣7️⃣\nScore: 52",
+                456,
+                10,
+            ),
+            (
+                "Daily Octordle #789\n8️⃣4️⃣\n5️⃣🔟\n🕛🟥\n6️⃣7️⃣\nScore: 75",
+                789,
+                0,
+            ),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+    }
+}
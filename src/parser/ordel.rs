@@ -0,0 +1,76 @@
+use anyhow::Result;
+use nom::{
+    bytes::complete::tag,
+    combinator,
+    sequence::{preceded, separated_pair, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, parse_grid, parse_score, wordle, Game, GameParser, GameResult};
+use crate::{
+    command::score::FIB,
+    validation::{validate_grid, Rejection},
+};
+
+pub(super) struct OrdelParser;
+
+// The swedish wordle, shared as "Ordel 123 3/6" with the same grid as wordle
+impl GameParser for OrdelParser {
+    fn game(&self) -> Game {
+        Game::Ordel
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Ordel "
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &FIB
+    }
+
+    fn validate(&self, result: &GameResult) -> Result<(), Rejection> {
+        validate_grid(result)
+    }
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        tuple((
+            preceded(
+                tuple((tag("Ordel "), combinator::opt(tag("#")))),
+                separated_pair(parse_day, tag(" "), parse_score),
+            ),
+            tag("/6"),
+            parse_grid(wordle::parse_row),
+        )),
+        |((day, score), _, guesses)| GameResult {
+            game: Game::Ordel,
+            day,
+            score,
+            hard_mode: false,
+            guesses,
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_ordel() {
+        let corpus = [
+            ("Ordel 123 2/6\n\n⬛🟨⬛⬛⬛\n🟩🟩🟩🟩🟩", 123, 2),
+            ("Ordel #456 X/6", 456, 0),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("Ordel abc 2/6").is_err());
+    }
+}
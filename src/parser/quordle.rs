@@ -0,0 +1,103 @@
+use anyhow::Result;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{multispace0, one_of},
+    combinator,
+    multi::count,
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, Game, GameParser, GameResult};
+
+// Four words in nine guesses, the last word takes at least four guesses
+const SCORING_TABLE: [u32; 10] = [0, 0, 0, 0, 13, 8, 5, 3, 2, 1];
+
+pub(super) struct QuordleParser;
+
+impl GameParser for QuordleParser {
+    fn game(&self) -> Game {
+        Game::Quordle
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Daily Quordle "
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &SCORING_TABLE
+    }
+}
+
+// The guess a word was solved on as a keycap emoji, 🟥 if it never was
+pub(super) fn parse_keycap(s: &str) -> IResult<&str, Option<i64>> {
+    preceded(
+        multispace0,
+        alt((
+            combinator::map(
+                terminated(
+                    one_of("123456789"),
+                    preceded(combinator::opt(tag("\u{fe0f}")), tag("\u{20e3}")),
+                ),
+                |digit| digit.to_digit(10).map(i64::from),
+            ),
+            combinator::value(None, tag("🟥")),
+        )),
+    )(s)
+}
+
+// Solving every word scores the guess the last one was solved on
+pub(super) fn score(words: &[Option<i64>]) -> i64 {
+    words
+        .iter()
+        .try_fold(0, |score, word| word.map(|word| word.max(score)))
+        .unwrap_or(0)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        preceded(
+            tag("Daily Quordle "),
+            tuple((parse_day, count(parse_keycap, 4))),
+        ),
+        |(day, words)| GameResult {
+            game: Game::Quordle,
+            day,
+            score: score(&words),
+            hard_mode: false,
+            guesses: vec![],
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_quordle() {
+        let corpus = [
+            (
+                "Daily Quordle 123\n6️⃣5️⃣\n9️⃣4️⃣\nm-w.com/games/quordle/",
+                123,
+                9,
+            ),
+            (
+                "Daily Quordle 1,024\n4\u{20e3}5\u{20e3}\n7\u{20e3}6\u{20e3}",
+                1024,
+                7,
+            ),
+            ("Daily Quordle 123\n6️⃣5️⃣\n🟥4️⃣", 123, 0),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("Daily Quordle 123\n6️⃣5️⃣").is_err());
+    }
+}
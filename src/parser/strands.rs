@@ -0,0 +1,61 @@
+use anyhow::Result;
+use nom::{bytes::complete::tag, combinator, sequence::preceded, IResult};
+
+use super::{finish, parse_day, Game, GameParser, GameResult};
+
+// Strands can not be failed, fewer hints is better
+const SCORING_TABLE: [u32; 6] = [0, 8, 5, 3, 2, 1];
+
+pub(super) struct StrandsParser;
+
+impl GameParser for StrandsParser {
+    fn game(&self) -> Game {
+        Game::Strands
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Strands #"
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &SCORING_TABLE
+    }
+
+    fn describe_score(&self, score: i64) -> String {
+        format!("{} ledtrådar", score - 1)
+    }
+}
+
+// Found words are 🔵 and the spangram 🟡, every 💡 is a hint used
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(preceded(tag("Strands #"), parse_day), |day| GameResult {
+        game: Game::Strands,
+        day,
+        // Shifted by one as zero means failure
+        score: s.matches('💡').count() as i64 + 1,
+        hard_mode: false,
+        guesses: vec![],
+    })(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_strands() {
+        let corpus = [
+            ("Strands #123\n“Stay sharp”\n🔵🔵🟡🔵\n🔵🔵🔵", 123, 1),
+            ("Strands #1,024\n“Good times”\n💡🔵🔵💡\n🟡🔵🔵🔵", 1024, 3),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("Strands #").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use nom::{
+    bytes::complete::tag,
+    character::complete::space0,
+    combinator,
+    multi::count,
+    sequence::{preceded, separated_pair, terminated, tuple},
+    IResult,
+};
+
+use super::{
+    finish, parse_day, parse_grid, parse_hard_mode, parse_score, parse_tile, Game, GameParser,
+    GameResult, Tile,
+};
+use crate::{
+    command::score::FIB,
+    utils::wordle_epoch,
+    validation::{validate_grid, Rejection},
+};
+
+pub(super) struct WordleParser;
+
+impl GameParser for WordleParser {
+    fn game(&self) -> Game {
+        Game::Wordle
+    }
+
+    fn prefix(&self) -> &'static str {
+        "Wordle "
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &FIB
+    }
+
+    fn epoch(&self) -> Option<NaiveDate> {
+        Some(wordle_epoch())
+    }
+
+    fn validate(&self, result: &GameResult) -> Result<(), Rejection> {
+        validate_grid(result)
+    }
+}
+
+// Assert that line starts with Wordle, but drop the parsed value
+fn parse_wordle_str(s: &str) -> IResult<&str, ()> {
+    combinator::map(tag("Wordle "), drop)(s)
+}
+
+fn parse_day_and_score(s: &str) -> IResult<&str, (i64, i64)> {
+    separated_pair(parse_day, tag(" "), parse_score)(s)
+}
+
+fn parse_wordle(s: &str) -> IResult<&str, (i64, i64)> {
+    preceded(parse_wordle_str, parse_day_and_score)(s)
+}
+
+fn parse_header(s: &str) -> IResult<&str, (i64, i64, bool)> {
+    combinator::map(
+        tuple((parse_wordle, tag("/6"), parse_hard_mode)),
+        |((day, score), _, hard_mode)| (day, score, hard_mode),
+    )(s)
+}
+
+pub(super) fn parse_row(s: &str) -> IResult<&str, Vec<Tile>> {
+    terminated(count(parse_tile, 5), space0)(s)
+}
+
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        tuple((parse_header, parse_grid(parse_row))),
+        |((day, score, hard_mode), guesses)| GameResult {
+            game: Game::Wordle,
+            day,
+            score,
+            hard_mode,
+            guesses,
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_wordle_str() {
+        assert_eq!(parse_wordle_str("Wordle "), Ok(("", ())));
+        assert!(parse_wordle_str("wordle").is_err());
+    }
+
+    #[test]
+    fn test_parse_day_and_score() {
+        assert_eq!(parse_day_and_score("547 3/6"), Ok(("/6", (547, 3))));
+
+        assert!(parse_day_and_score("547 7/6").is_err());
+    }
+
+    #[test]
+    fn test_parse_wordle() {
+        assert_eq!(parse_wordle("Wordle 547 3/6"), Ok(("/6", (547, 3))));
+        assert!(parse_wordle("Wordle 547 7/6").is_err());
+        assert!(parse_wordle("wordle 547 7/6").is_err());
+        assert!(parse_wordle("Wordle foo 7/6").is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse_header("Wordle 547 3/6"), Ok(("", (547, 3, false))));
+        assert_eq!(parse_header("Wordle 547 3/6*"), Ok(("", (547, 3, true))));
+        assert_eq!(parse_header("Wordle 547 X/6*"), Ok(("", (547, 0, true))));
+        assert!(parse_header("Wordle 547 3/5").is_err());
+    }
+
+    #[test]
+    fn test_parse_row() {
+        use Tile::*;
+        assert_eq!(
+            parse_row("⬛🟨⬛⬛🟩"),
+            Ok(("", vec![Absent, Present, Absent, Absent, Correct]))
+        );
+        assert_eq!(
+            parse_row("⬜🟦⬜⬜🟧"),
+            Ok(("", vec![Absent, Present, Absent, Absent, Correct]))
+        );
+        assert!(parse_row("⬛🟨⬛⬛").is_err());
+    }
+
+    #[test]
+    fn test_parse_msg() {
+        use Tile::*;
+        let dark = "Wordle 547 3/6\n\n⬛🟨⬛⬛⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩";
+        let light = "Wordle 547 3/6\n\n⬜🟨⬜⬜⬜\n🟩🟩⬜🟩🟩\n🟩🟩🟩🟩🟩";
+        let contrast = "Wordle 547 3/6\r\n\r\n⬛🟦⬛⬛⬛\r\n🟧🟧⬛🟧🟧\r\n🟧🟧🟧🟧🟧";
+        let expected = GameResult {
+            game: Game::Wordle,
+            day: 547,
+            score: 3,
+            hard_mode: false,
+            guesses: vec![
+                vec![Absent, Present, Absent, Absent, Absent],
+                vec![Correct, Correct, Absent, Correct, Correct],
+                vec![Correct; 5],
+            ],
+        };
+        for msg in [dark, light, contrast] {
+            assert_eq!(parse_msg(msg).unwrap(), expected);
+        }
+
+        let hard = parse_msg("Wordle 547 1/6*\n\n🟩🟩🟩🟩🟩\nTrailing text").unwrap();
+        assert!(hard.hard_mode);
+        assert_eq!(hard.guesses, vec![vec![Correct; 5]]);
+
+        let no_grid = parse_msg("Wordle 547 4/6").unwrap();
+        assert!(no_grid.guesses.is_empty());
+
+        assert!(parse_msg("Wordle 547 7/6").is_err());
+    }
+
+    #[test]
+    fn test_share_corpus() {
+        let corpus = [
+            ("Wordle 547 3/6\n\n⬛🟨⬛⬛⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩", 547, 3),
+            ("Wordle 999 X/6\n\n⬛⬛⬛⬛⬛\n⬛🟨⬛⬛⬛\n🟩⬛⬛⬛⬛\n🟩🟩⬛⬛⬛\n🟩🟩🟩⬛🟩\n🟩🟩🟩⬛🟩", 999, 0),
+            ("Wordle 1,000 2/6\n\n⬜🟨⬜🟩⬜\n🟩🟩🟩🟩🟩", 1000, 2),
+            ("Wordle 1,234 4/6*\n\n⬛⬛🟨⬛⬛\n⬛🟩⬛🟨⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩", 1234, 4),
+            ("Wordle 1.234 4/6\n\n⬛⬛🟦⬛⬛\n⬛🟧⬛🟦⬛\n🟧🟧⬛🟧🟧\n🟧🟧🟧🟧🟧", 1234, 4),
+            ("Wordle 1 234 5/6\n\n⬛⬛⬛⬛⬛\n⬛⬛🟨⬛⬛\n⬛🟩⬛🟨⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩", 1234, 5),
+            ("Wordle 1\u{a0}234 1/6\n\n🟩🟩🟩🟩🟩", 1234, 1),
+            ("Wordle 1\u{202f}234 6/6\n\n⬛⬛⬛⬛⬛\n⬛⬛⬛⬛⬛\n⬛⬛🟨⬛⬛\n⬛🟩⬛🟨⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩", 1234, 6),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+    }
+}
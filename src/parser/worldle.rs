@@ -0,0 +1,77 @@
+use anyhow::Result;
+use nom::{
+    bytes::complete::{tag, take_until},
+    combinator,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+use super::{finish, parse_day, parse_score, Game, GameParser, GameResult};
+use crate::command::score::FIB;
+
+pub(super) struct WorldleParser;
+
+impl GameParser for WorldleParser {
+    fn game(&self) -> Game {
+        Game::Worldle
+    }
+
+    fn prefix(&self) -> &'static str {
+        "#Worldle #"
+    }
+
+    fn parse(&self, s: &str) -> Result<GameResult> {
+        finish(parse_result(s))
+    }
+
+    fn scoring_table(&self) -> &'static [u32] {
+        &FIB
+    }
+}
+
+// Newer share messages include the date, "#Worldle #123 (14.03.2024) 4/6 (100%)"
+fn parse_date(s: &str) -> IResult<&str, &str> {
+    terminated(delimited(tag("("), take_until(")"), tag(")")), tag(" "))(s)
+}
+
+// The grid shows distance and direction to the country rather than letters
+fn parse_result(s: &str) -> IResult<&str, GameResult> {
+    combinator::map(
+        tuple((
+            preceded(tag("#Worldle #"), parse_day),
+            tag(" "),
+            combinator::opt(parse_date),
+            parse_score,
+            tag("/6"),
+        )),
+        |(day, _, _, score, _)| GameResult {
+            game: Game::Worldle,
+            day,
+            score,
+            hard_mode: false,
+            guesses: vec![],
+        },
+    )(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_msg;
+
+    #[test]
+    fn test_parse_worldle() {
+        let corpus = [
+            (
+                "#Worldle #123 4/6 (100%)\n🟩🟩🟩🟨⬜⬅️\n🟩🟩🟩🟩🟨↘️\n🟩🟩🟩🟩⬜⬆️\n🟩🟩🟩🟩🟩🎉",
+                123,
+                4,
+            ),
+            ("#Worldle #800 (14.03.2024) X/6 (93%)", 800, 0),
+        ];
+        for (msg, day, score) in corpus {
+            let result = parse_msg(msg).unwrap();
+            assert_eq!((result.day, result.score), (day, score), "{msg}");
+        }
+        assert!(parse_msg("#Worldle #123 7/6").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::f64::consts::PI;
+
+// Conversion between the Glicko and the Glicko-2 scale
+const SCALE: f64 = 173.7178;
+// Constrains the change in volatility over time
+const TAU: f64 = 0.5;
+const EPSILON: f64 = 0.000001;
+
+/// A Glicko-2 rating, on the familiar Glicko scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Rating {
+    pub(crate) rating: f64,
+    pub(crate) deviation: f64,
+    pub(crate) volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn expected(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+impl Rating {
+    fn mu(&self) -> f64 {
+        (self.rating - 1500.0) / SCALE
+    }
+
+    fn phi(&self) -> f64 {
+        self.deviation / SCALE
+    }
+
+    /// The rating after a period without any games, only the deviation grows
+    pub(crate) fn idle(&self) -> Rating {
+        let phi = (self.phi().powi(2) + self.volatility.powi(2)).sqrt();
+        Rating {
+            deviation: (phi * SCALE).min(Rating::default().deviation),
+            ..*self
+        }
+    }
+
+    /// The rating after a period, given the opponents and the outcome against them
+    /// where 1.0 is a win, 0.5 a draw and 0.0 a loss
+    pub(crate) fn update(&self, games: &[(Rating, f64)]) -> Rating {
+        if games.is_empty() {
+            return self.idle();
+        }
+        let (mu, phi, sigma) = (self.mu(), self.phi(), self.volatility);
+        let v = 1.0
+            / games
+                .iter()
+                .map(|(opponent, _)| {
+                    let e = expected(mu, opponent.mu(), opponent.phi());
+                    g(opponent.phi()).powi(2) * e * (1.0 - e)
+                })
+                .sum::<f64>();
+        let improvement: f64 = games
+            .iter()
+            .map(|(opponent, outcome)| {
+                g(opponent.phi()) * (outcome - expected(mu, opponent.mu(), opponent.phi()))
+            })
+            .sum();
+        let delta = v * improvement;
+
+        // The new volatility, found with the Illinois algorithm
+        let a = sigma.powi(2).ln();
+        let f = |x: f64| {
+            x.exp() * (delta.powi(2) - phi.powi(2) - v - x.exp())
+                / (2.0 * (phi.powi(2) + v + x.exp()).powi(2))
+                - (x - a) / TAU.powi(2)
+        };
+        let mut big_a = a;
+        let mut big_b = match delta.powi(2) > phi.powi(2) + v {
+            true => (delta.powi(2) - phi.powi(2) - v).ln(),
+            false => {
+                let mut k = 1.0;
+                while f(a - k * TAU) < 0.0 {
+                    k += 1.0;
+                }
+                a - k * TAU
+            }
+        };
+        let (mut f_a, mut f_b) = (f(big_a), f(big_b));
+        while (big_b - big_a).abs() > EPSILON {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0.0 {
+                (big_a, f_a) = (big_b, f_b);
+            } else {
+                f_a /= 2.0;
+            }
+            (big_b, f_b) = (big_c, f_c);
+        }
+        let volatility = (big_a / 2.0).exp();
+
+        let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+        let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi.powi(2) * improvement;
+        Rating {
+            rating: new_mu * SCALE + 1500.0,
+            deviation: new_phi * SCALE,
+            volatility,
+        }
+    }
+}
+
+/// Outcome of a result against another on the same puzzle, a lower score wins and zero is a failure
+pub(crate) fn outcome(score: i64, other: i64) -> f64 {
+    let rank = |score: i64| match score {
+        0 => i64::MAX,
+        score => score,
+    };
+    match rank(score).cmp(&rank(other)) {
+        std::cmp::Ordering::Less => 1.0,
+        std::cmp::Ordering::Equal => 0.5,
+        std::cmp::Ordering::Greater => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        // The example from the Glicko-2 paper by Mark Glickman
+        let player = Rating {
+            rating: 1500.0,
+            deviation: 200.0,
+            volatility: 0.06,
+        };
+        let opponent = |rating, deviation| Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        };
+        let updated = player.update(&[
+            (opponent(1400.0, 30.0), 1.0),
+            (opponent(1550.0, 100.0), 0.0),
+            (opponent(1700.0, 300.0), 0.0),
+        ]);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{updated:?}");
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{updated:?}");
+        assert!(
+            (updated.volatility - 0.05999).abs() < 0.00001,
+            "{updated:?}"
+        );
+    }
+
+    #[test]
+    fn test_idle() {
+        let player = Rating {
+            rating: 1500.0,
+            deviation: 50.0,
+            volatility: 0.06,
+        };
+        let idle = player.update(&[]);
+        assert_eq!(idle.rating, player.rating);
+        assert!(idle.deviation > player.deviation);
+        // The deviation never grows past that of a new player
+        assert_eq!(Rating::default().idle(), Rating::default());
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(outcome(2, 4), 1.0);
+        assert_eq!(outcome(4, 4), 0.5);
+        assert_eq!(outcome(0, 6), 0.0);
+        assert_eq!(outcome(6, 0), 1.0);
+        assert_eq!(outcome(0, 0), 0.5);
+    }
+}
@@ -1,16 +1,23 @@
-use serenity::model::prelude::MessageId;
+use serenity::model::prelude::{ChannelId, MessageId};
 
-use crate::player::Player;
+use crate::{parser::Game, player::Player};
 
 #[allow(dead_code)]
 #[derive(sqlx::FromRow)]
 pub(crate) struct Scoresheet {
     pub(crate) id: i64,
     pub(crate) msg_id: i64,
+    pub(crate) guild_id: i64,
+    pub(crate) channel_id: i64,
     pub(crate) day: i64,
     pub(crate) player_id: i64,
     pub(crate) score: i64,
-    pub(crate) cup_number: String,
+    pub(crate) season_id: i64,
+    // One line per guess, see `parser::Tile::as_char`
+    pub(crate) grid: String,
+    pub(crate) hard_mode: bool,
+    pub(crate) deleted_at: Option<i64>,
+    pub(crate) game: String,
 }
 
 impl Scoresheet {
@@ -22,7 +29,19 @@ impl Scoresheet {
         MessageId(self.msg_id as u64)
     }
 
+    pub(crate) fn channel_id(&self) -> ChannelId {
+        ChannelId(self.channel_id as u64)
+    }
+
     pub(crate) fn score(&self) -> i64 {
         self.score
     }
+
+    pub(crate) fn game(&self) -> Game {
+        self.game.parse().expect("Unknown game in score sheet")
+    }
+
+    pub(crate) fn hard_mode(&self) -> bool {
+        self.hard_mode
+    }
 }
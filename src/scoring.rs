@@ -0,0 +1,548 @@
+use std::{cmp::Reverse, collections::HashMap, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+
+use crate::{command::score::points, parser::Game, player::Player};
+
+/// Turns results into cup points
+pub(crate) trait ScoringRule: Sync {
+    /// Points of a single result, the field being every score posted for the same puzzle.
+    /// The bonus is what the result earns on top for being played in hard mode.
+    fn points(&self, game: Game, score: i64, bonus: u32, field: &[i64]) -> u32;
+
+    /// Combines the points of all results of a player in a cup
+    fn total(&self, points: &[u32]) -> u32 {
+        points.iter().sum()
+    }
+
+    /// The total after losing the penalty, as if taken off the points of the results
+    fn penalised_total(&self, points: &[u32], penalty: u32) -> u32 {
+        self.total(points).saturating_sub(penalty)
+    }
+}
+
+// The scoring table of the game, weighing early solves the most
+struct Fibonacci;
+
+impl ScoringRule for Fibonacci {
+    fn points(&self, game: Game, score: i64, bonus: u32, _field: &[i64]) -> u32 {
+        points(game, score, bonus)
+    }
+}
+
+// One point for every attempt left when solved
+struct Linear;
+
+impl ScoringRule for Linear {
+    fn points(&self, game: Game, score: i64, bonus: u32, _field: &[i64]) -> u32 {
+        let max_score = game.parser().scoring_table().len() as i64 - 1;
+        match score {
+            0 => 0,
+            score => (max_score + 1 - score).max(1) as u32 + bonus,
+        }
+    }
+}
+
+// Like the official statistics, the average result counts rather than the number of puzzles played
+struct OfficialAverage;
+
+impl ScoringRule for OfficialAverage {
+    fn points(&self, game: Game, score: i64, bonus: u32, field: &[i64]) -> u32 {
+        Linear.points(game, score, bonus, field)
+    }
+
+    // The average in hundredths, to keep the decimals
+    fn total(&self, points: &[u32]) -> u32 {
+        self.penalised_total(points, 0)
+    }
+
+    fn penalised_total(&self, points: &[u32], penalty: u32) -> u32 {
+        match points.len() {
+            0 => 0,
+            len => 100 * points.iter().sum::<u32>().saturating_sub(penalty) / len as u32,
+        }
+    }
+}
+
+// One point for every player with a worse result on the same puzzle
+struct BeatTheField;
+
+impl ScoringRule for BeatTheField {
+    fn points(&self, _game: Game, score: i64, bonus: u32, field: &[i64]) -> u32 {
+        if score == 0 {
+            return 0;
+        }
+        field
+            .iter()
+            .filter(|other| **other == 0 || **other > score)
+            .count() as u32
+            + bonus
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) enum Scoring {
+    #[default]
+    Fibonacci,
+    Linear,
+    OfficialAverage,
+    BeatTheField,
+}
+
+impl Scoring {
+    pub(crate) const ALL: [Scoring; 4] = [
+        Scoring::Fibonacci,
+        Scoring::Linear,
+        Scoring::OfficialAverage,
+        Scoring::BeatTheField,
+    ];
+
+    /// Identifier used in the database and as command option value
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Scoring::Fibonacci => "fibonacci",
+            Scoring::Linear => "linear",
+            Scoring::OfficialAverage => "average",
+            Scoring::BeatTheField => "field",
+        }
+    }
+
+    pub(crate) fn rule(&self) -> &'static dyn ScoringRule {
+        match self {
+            Scoring::Fibonacci => &Fibonacci,
+            Scoring::Linear => &Linear,
+            Scoring::OfficialAverage => &OfficialAverage,
+            Scoring::BeatTheField => &BeatTheField,
+        }
+    }
+}
+
+impl Display for Scoring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scoring::Fibonacci => f.write_str("Fibonacci"),
+            Scoring::Linear => f.write_str("Linjär"),
+            Scoring::OfficialAverage => f.write_str("Snitt"),
+            Scoring::BeatTheField => f.write_str("Slå fältet"),
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Scoring::ALL
+            .into_iter()
+            .find(|scoring| scoring.name() == s)
+            .ok_or_else(|| anyhow!("Unknown scoring rule {s}"))
+    }
+}
+
+/// How the points of the players in a cup are ranked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Ranking {
+    /// The points combined by the scoring rule, rewarding playing often
+    #[default]
+    Sum,
+    /// Mean points per game in hundredths, leaving out players with fewer games.
+    /// Shrinkage pulls the mean towards that of the whole field, as if every player
+    /// had played the minimum number of games more at the field mean.
+    Average { min_games: u32, shrinkage: bool },
+}
+
+impl Ranking {
+    /// Ranks the points of every player, best player first and ties by player id. The
+    /// penalties are taken off the points of the players, e.g. for skipped days.
+    pub(crate) fn rank(
+        &self,
+        rule: &dyn ScoringRule,
+        points: &HashMap<i64, Vec<u32>>,
+        penalties: &HashMap<i64, u32>,
+    ) -> Vec<(Player, u32)> {
+        let penalty = |player_id: &i64| penalties.get(player_id).copied().unwrap_or(0);
+        let mut leader_board: Vec<(Player, u32)> = match *self {
+            Ranking::Sum => points
+                .iter()
+                .map(|(player_id, points)| {
+                    let total = rule.penalised_total(points, penalty(player_id));
+                    (Player::from(*player_id), total)
+                })
+                .collect(),
+            Ranking::Average {
+                min_games,
+                shrinkage,
+            } => {
+                let field_games: usize = points.values().map(Vec::len).sum();
+                let field_points = points
+                    .values()
+                    .flatten()
+                    .sum::<u32>()
+                    .saturating_sub(penalties.values().sum());
+                let field_mean = field_points as f64 / field_games.max(1) as f64;
+                let prior = match shrinkage {
+                    true => min_games.max(1) as f64,
+                    false => 0.0,
+                };
+                points
+                    .iter()
+                    .filter(|(_, points)| !points.is_empty() && points.len() as u32 >= min_games)
+                    .map(|(player_id, points)| {
+                        let sum = points
+                            .iter()
+                            .sum::<u32>()
+                            .saturating_sub(penalty(player_id));
+                        let sum = sum as f64;
+                        let mean = (prior * field_mean + sum) / (prior + points.len() as f64);
+                        (Player::from(*player_id), (100.0 * mean).round() as u32)
+                    })
+                    .collect()
+            }
+        };
+        leader_board.retain(|(_, score)| *score > 0);
+        leader_board.sort_by_key(|x| (Reverse(x.1), x.0.id));
+        leader_board
+    }
+}
+
+impl Display for Ranking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ranking::Sum => f.write_str("summa"),
+            Ranking::Average {
+                min_games,
+                shrinkage: false,
+            } => write!(f, "snitt, minst {min_games} spel"),
+            Ranking::Average {
+                min_games,
+                shrinkage: true,
+            } => write!(f, "krympt snitt, minst {min_games} spel"),
+        }
+    }
+}
+
+/// What a player did in a cup, to break ties with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Record {
+    /// Days with the best result of everyone
+    pub(crate) golds: u32,
+    pub(crate) failures: u32,
+    pub(crate) points: Vec<u32>,
+    /// The message id of the last result worth any points, the moment the total was reached
+    pub(crate) reached: Option<i64>,
+}
+
+impl Record {
+    pub(crate) fn add(&mut self, score: i64, points: u32, gold: bool, msg_id: Option<i64>) {
+        self.golds += gold as u32;
+        self.failures += (score == 0) as u32;
+        self.points.push(points);
+        if points > 0 {
+            self.reached = self.reached.max(msg_id);
+        }
+    }
+}
+
+/// Separates players tied for the win
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TieBreaker {
+    Golds,
+    Average,
+    FewestFailures,
+    EarliestToTotal,
+}
+
+impl TieBreaker {
+    pub(crate) const ALL: [TieBreaker; 4] = [
+        TieBreaker::Golds,
+        TieBreaker::Average,
+        TieBreaker::FewestFailures,
+        TieBreaker::EarliestToTotal,
+    ];
+
+    /// Identifier used in the database
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            TieBreaker::Golds => "golds",
+            TieBreaker::Average => "average",
+            TieBreaker::FewestFailures => "failures",
+            TieBreaker::EarliestToTotal => "earliest",
+        }
+    }
+
+    /// Identifier used in the admin command
+    pub(crate) fn command_name(&self) -> &'static str {
+        match self {
+            TieBreaker::Golds => "guld",
+            TieBreaker::Average => "snitt",
+            TieBreaker::FewestFailures => "x",
+            TieBreaker::EarliestToTotal => "först",
+        }
+    }
+
+    // Lower is better
+    fn key(&self, record: &Record) -> i64 {
+        match self {
+            TieBreaker::Golds => -(record.golds as i64),
+            TieBreaker::Average => match record.points.len() {
+                0 => 0,
+                len => -(1000 * record.points.iter().sum::<u32>() as i64 / len as i64),
+            },
+            TieBreaker::FewestFailures => record.failures as i64,
+            TieBreaker::EarliestToTotal => record.reached.unwrap_or(i64::MAX),
+        }
+    }
+}
+
+impl Display for TieBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TieBreaker::Golds => f.write_str("flest guld"),
+            TieBreaker::Average => f.write_str("bäst snitt"),
+            TieBreaker::FewestFailures => f.write_str("färst X"),
+            TieBreaker::EarliestToTotal => f.write_str("först till totalen"),
+        }
+    }
+}
+
+impl FromStr for TieBreaker {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        TieBreaker::ALL
+            .into_iter()
+            .find(|tie_breaker| tie_breaker.name() == s || tie_breaker.command_name() == s)
+            .ok_or_else(|| anyhow!("Unknown tie breaker {s}"))
+    }
+}
+
+/// The players sharing the win, everyone tied at the top of the leader board
+/// that the tie breakers can not separate
+pub(crate) fn winners(
+    leader_board: &[(Player, u32)],
+    records: &HashMap<i64, Record>,
+    tie_breakers: &[TieBreaker],
+) -> Vec<Player> {
+    let Some((_, best)) = leader_board.first() else {
+        return vec![];
+    };
+    let mut winners: Vec<Player> = leader_board
+        .iter()
+        .take_while(|(_, score)| score == best)
+        .map(|(player, _)| *player)
+        .collect();
+    let no_record = Record::default();
+    for tie_breaker in tie_breakers {
+        let key = |player: &Player| {
+            tie_breaker.key(records.get(&(player.id as i64)).unwrap_or(&no_record))
+        };
+        let best = winners.iter().map(key).min().unwrap();
+        winners.retain(|player| key(player) == best);
+    }
+    winners
+}
+
+/// The final placement of everyone on the leader board as (player, placement, points).
+/// The winners share the first place, everyone else is placed after all winners and
+/// everyone with more points.
+pub(crate) fn standings(
+    leader_board: &[(Player, u32)],
+    winners: &[Player],
+) -> Vec<(Player, u32, u32)> {
+    leader_board
+        .iter()
+        .map(|(player, points)| {
+            let placement = match winners.contains(player) {
+                true => 1,
+                false => {
+                    let ahead = leader_board.iter().filter(|(other, other_points)| {
+                        winners.contains(other) || other_points > points
+                    });
+                    1 + ahead.count() as u32
+                }
+            };
+            (*player, placement, *points)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let field = [2, 4, 4, 0];
+        let points = |scoring: Scoring, score| {
+            let rule = scoring.rule();
+            rule.points(Game::Wordle, score, 0, &field)
+        };
+        assert_eq!(points(Scoring::Fibonacci, 2), 8);
+        assert_eq!(points(Scoring::Linear, 2), 5);
+        assert_eq!(points(Scoring::Linear, 6), 1);
+        assert_eq!(points(Scoring::Linear, 0), 0);
+        assert_eq!(points(Scoring::BeatTheField, 2), 3);
+        assert_eq!(points(Scoring::BeatTheField, 4), 1);
+        assert_eq!(points(Scoring::BeatTheField, 0), 0);
+
+        assert_eq!(Scoring::Fibonacci.rule().total(&[5, 3]), 8);
+        assert_eq!(Scoring::OfficialAverage.rule().total(&[5, 2]), 350);
+        assert_eq!(Scoring::OfficialAverage.rule().total(&[]), 0);
+        // A skipped day is a result without points, the penalty is taken off on top
+        let rule = Scoring::Fibonacci.rule();
+        assert_eq!(rule.penalised_total(&[5, 3, 0], 2), 6);
+        assert_eq!(rule.penalised_total(&[1, 0], 2), 0);
+        let rule = Scoring::OfficialAverage.rule();
+        assert_eq!(rule.penalised_total(&[5, 3, 0], 2), 200);
+    }
+
+    #[test]
+    fn test_hard_mode_bonus() {
+        let field = [2, 4, 4, 0];
+        // Every rule adds the bonus to solved games only
+        for (scoring, solved) in [
+            (Scoring::Fibonacci, 8),
+            (Scoring::Linear, 5),
+            (Scoring::OfficialAverage, 5),
+            (Scoring::BeatTheField, 3),
+        ] {
+            let rule = scoring.rule();
+            assert_eq!(rule.points(Game::Wordle, 2, 2, &field), solved + 2);
+            assert_eq!(rule.points(Game::Wordle, 0, 2, &field), 0);
+        }
+    }
+
+    #[test]
+    fn test_ranking() {
+        let points = HashMap::from([(1, vec![13, 13]), (2, vec![8, 8, 8, 8]), (3, vec![0])]);
+        let rule = Scoring::Fibonacci.rule();
+        assert_eq!(
+            Ranking::Sum.rank(rule, &points, &HashMap::new()),
+            vec![(Player::from(2), 32), (Player::from(1), 26)]
+        );
+        let penalties = HashMap::from([(2, 10)]);
+        assert_eq!(
+            Ranking::Sum.rank(rule, &points, &penalties),
+            vec![(Player::from(1), 26), (Player::from(2), 22)]
+        );
+        let average = |min_games, shrinkage| {
+            Ranking::Average {
+                min_games,
+                shrinkage,
+            }
+            .rank(rule, &points, &HashMap::new())
+        };
+        assert_eq!(
+            average(0, false),
+            vec![(Player::from(1), 1300), (Player::from(2), 800)]
+        );
+        assert_eq!(average(3, false), vec![(Player::from(2), 800)]);
+        // The field mean is 58 / 7, a few games are pulled towards it more than many
+        assert_eq!(
+            average(2, true),
+            vec![(Player::from(1), 1064), (Player::from(2), 810)]
+        );
+    }
+
+    #[test]
+    fn test_winners() {
+        let leader_board = [
+            (Player::from(1), 20),
+            (Player::from(2), 20),
+            (Player::from(3), 20),
+            (Player::from(4), 15),
+        ];
+        let record = |golds, failures, points: &[u32], reached| Record {
+            golds,
+            failures,
+            points: points.to_vec(),
+            reached: Some(reached),
+        };
+        let records = HashMap::from([
+            (1, record(2, 1, &[8, 8, 4, 0], 30)),
+            (2, record(2, 0, &[8, 8, 4], 20)),
+            (3, record(1, 0, &[5, 5, 5, 5], 10)),
+            (4, record(3, 0, &[5, 5, 5], 40)),
+        ]);
+        let winners = |tie_breakers: &[TieBreaker]| winners(&leader_board, &records, tie_breakers);
+        assert_eq!(
+            winners(&[]),
+            vec![Player::from(1), Player::from(2), Player::from(3)]
+        );
+        assert_eq!(
+            winners(&[TieBreaker::Golds]),
+            vec![Player::from(1), Player::from(2)]
+        );
+        assert_eq!(
+            winners(&[TieBreaker::Golds, TieBreaker::FewestFailures]),
+            vec![Player::from(2)]
+        );
+        assert_eq!(winners(&[TieBreaker::Average]), vec![Player::from(2)]);
+        assert_eq!(
+            winners(&[TieBreaker::EarliestToTotal]),
+            vec![Player::from(3)]
+        );
+        assert!(super::winners(&[], &records, &TieBreaker::ALL).is_empty());
+    }
+
+    #[test]
+    fn test_standings() {
+        let leader_board = [
+            (Player::from(1), 20),
+            (Player::from(2), 20),
+            (Player::from(3), 15),
+            (Player::from(4), 15),
+            (Player::from(5), 10),
+        ];
+        // A tie broken at the top still leaves the runner up second
+        assert_eq!(
+            standings(&leader_board, &[Player::from(2)]),
+            vec![
+                (Player::from(1), 2, 20),
+                (Player::from(2), 1, 20),
+                (Player::from(3), 3, 15),
+                (Player::from(4), 3, 15),
+                (Player::from(5), 5, 10),
+            ]
+        );
+        let shared = standings(&leader_board, &[Player::from(1), Player::from(2)]);
+        let placements: Vec<u32> = shared.iter().map(|(_, placement, _)| *placement).collect();
+        assert_eq!(placements, vec![1, 1, 3, 3, 5]);
+        assert!(standings(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_record() {
+        let mut record = Record::default();
+        record.add(3, 5, true, Some(10));
+        record.add(0, 0, false, Some(30));
+        record.add(4, 3, false, Some(20));
+        assert_eq!(
+            record,
+            Record {
+                golds: 1,
+                failures: 1,
+                points: vec![5, 0, 3],
+                reached: Some(20),
+            }
+        );
+    }
+
+    #[test]
+    fn test_scoring_names() {
+        for scoring in Scoring::ALL {
+            assert_eq!(scoring.name().parse::<Scoring>().unwrap(), scoring);
+        }
+        assert!("poker".parse::<Scoring>().is_err());
+        for tie_breaker in TieBreaker::ALL {
+            assert_eq!(
+                tie_breaker.name().parse::<TieBreaker>().unwrap(),
+                tie_breaker
+            );
+            assert_eq!(
+                tie_breaker.command_name().parse::<TieBreaker>().unwrap(),
+                tie_breaker
+            );
+        }
+    }
+}
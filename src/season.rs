@@ -0,0 +1,186 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+/// A cup, running from its first to its last day inclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Season {
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) start: NaiveDate,
+    pub(crate) end: NaiveDate,
+}
+
+impl Season {
+    // From a row of the season table, where the days are stored as ISO 8601 dates
+    pub(crate) fn from_columns(id: i64, name: String, start: &str, end: &str) -> Result<Season> {
+        Ok(Season {
+            id,
+            name,
+            start: start.parse()?,
+            end: end.parse()?,
+        })
+    }
+
+    // Monthly seasons are named YYYY-MM, the old "2023/1" style is still understood
+    pub(crate) fn normalise_name(name: &str) -> String {
+        let Some((year, month)) = name.trim().split_once('/') else {
+            return name.trim().to_string();
+        };
+        match (year.parse::<i32>(), month.parse::<u32>()) {
+            (Ok(year), Ok(month)) if (1..=12).contains(&month) => format!("{year}-{month:02}"),
+            _ => name.trim().to_string(),
+        }
+    }
+}
+
+// Seasons never overlap, so they are in the order they start
+impl Ord for Season {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.start, self.id).cmp(&(other.start, other.id))
+    }
+}
+
+impl PartialOrd for Season {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The length of the seasons that are created when a result is posted outside of any season
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Period {
+    Weekly,
+    #[default]
+    Monthly,
+    Quarterly,
+}
+
+impl Period {
+    pub(crate) const ALL: [Period; 3] = [Period::Weekly, Period::Monthly, Period::Quarterly];
+
+    /// Identifier used in the database and as command option value
+    pub(crate) fn value(&self) -> &'static str {
+        match self {
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+            Period::Quarterly => "quarterly",
+        }
+    }
+
+    // Read from SEASON_PERIOD, a month unless set. Guilds may configure their own.
+    pub(crate) fn configured() -> Period {
+        std::env::var("SEASON_PERIOD")
+            .ok()
+            .and_then(|period| period.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// The first and last day of the period containing the date
+    pub(crate) fn window(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let start = match self {
+            Period::Weekly => date - Days::new(date.weekday().num_days_from_monday().into()),
+            Period::Monthly => date.with_day(1).unwrap(),
+            Period::Quarterly => {
+                NaiveDate::from_ymd_opt(date.year(), (date.month0() / 3) * 3 + 1, 1).unwrap()
+            }
+        };
+        let end = match self {
+            Period::Weekly => start + Days::new(6),
+            Period::Monthly => start + Months::new(1) - Days::new(1),
+            Period::Quarterly => start + Months::new(3) - Days::new(1),
+        };
+        (start, end)
+    }
+
+    /// Name of the period starting on the date
+    pub(crate) fn name(&self, start: NaiveDate) -> String {
+        match self {
+            Period::Weekly => format!("{} v{}", start.iso_week().year(), start.iso_week().week()),
+            Period::Monthly => start.format("%Y-%m").to_string(),
+            Period::Quarterly => format!("{} K{}", start.year(), start.month0() / 3 + 1),
+        }
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::Weekly => f.write_str("vecka"),
+            Period::Monthly => f.write_str("månad"),
+            Period::Quarterly => f.write_str("kvartal"),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Period::ALL
+            .into_iter()
+            .find(|period| period.value() == s)
+            .ok_or_else(|| anyhow!("Unknown season period {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_window() {
+        assert_eq!(
+            Period::Weekly.window(date(2023, 11, 16)),
+            (date(2023, 11, 13), date(2023, 11, 19))
+        );
+        assert_eq!(
+            Period::Monthly.window(date(2024, 2, 10)),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(
+            Period::Quarterly.window(date(2023, 12, 31)),
+            (date(2023, 10, 1), date(2023, 12, 31))
+        );
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Period::Weekly.name(date(2023, 1, 2)), "2023 v1");
+        assert_eq!(Period::Monthly.name(date(2023, 1, 1)), "2023-01");
+        assert_eq!(Period::Monthly.name(date(2023, 11, 1)), "2023-11");
+        assert_eq!(Period::Quarterly.name(date(2023, 4, 1)), "2023 K2");
+    }
+
+    #[test]
+    fn test_normalise_name() {
+        assert_eq!(Season::normalise_name("2023/1"), "2023-01");
+        assert_eq!(Season::normalise_name("2024/11"), "2024-11");
+        assert_eq!(Season::normalise_name("2023-01"), "2023-01");
+        assert_eq!(Season::normalise_name("2023/13"), "2023/13");
+        assert_eq!(Season::normalise_name(" Sommarcupen "), "Sommarcupen");
+    }
+
+    #[test]
+    fn test_order() {
+        let season = |id, start| Season {
+            id,
+            name: String::new(),
+            start,
+            end: start,
+        };
+        let mut seasons = [
+            season(1, date(2024, 11, 1)),
+            season(2, date(2024, 2, 1)),
+            season(3, date(2023, 12, 1)),
+        ];
+        seasons.sort();
+        let ids: Vec<i64> = seasons.iter().map(|season| season.id).collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+    }
+}
@@ -1,129 +1,148 @@
-use std::cmp::Ordering;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 use std::time::UNIX_EPOCH;
 
 use chrono::DateTime;
-use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::TimeZone;
 use chrono::Utc;
+use chrono_tz::Tz;
 
-pub fn cup_number_from_unixtime(unixtime: i64) -> String {
-    // Creates a new SystemTime from the specified number of whole seconds
-    let d = UNIX_EPOCH + Duration::from_secs(unixtime as u64);
-    // Create DateTime from SystemTime
-    let datetime = DateTime::<Utc>::from(d);
-    datetime.year().to_string() + &datetime.month().to_string()
+// How many days off a posted puzzle may be, on top of the time zone spread
+const DAY_TOLERANCE: i64 = 1;
+
+// Puzzle number zero was published on 2021-06-19
+pub fn wordle_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2021, 6, 19).unwrap()
 }
 
-pub fn current_cup_number() -> String {
-    let current_date = chrono::Utc::now().date_naive();
-    current_date.year().to_string() + &current_date.month().to_string()
+pub fn day_from_date(date: NaiveDate) -> i64 {
+    date.signed_duration_since(wordle_epoch()).num_days()
 }
 
-pub fn current_cup_number_cute_format() -> String {
-    let current_date = chrono::Utc::now().date_naive();
-    format!("{}/{}", current_date.year(), current_date.month())
+pub fn date_from_day(day: i64) -> NaiveDate {
+    wordle_epoch() + chrono::Duration::days(day)
 }
 
-// Upserts a new potential high-score into a sorted list of high-scores of length three
-pub fn recalcualate_high_scores(high_scores: [Option<i64>; 3], score: i64) -> [Option<i64>; 3] {
-    match (high_scores[0], high_scores[1], high_scores[2]) {
-        (None, None, None) => [Some(score), None, None],
-        (Some(gold), None, None) => match gold.cmp(&score) {
-            Ordering::Greater => [Some(score), Some(gold), None],
-            Ordering::Equal => [Some(gold), None, None],
-            Ordering::Less => [Some(gold), Some(score), None],
-        },
-        (Some(gold), Some(silver), None) => match (gold.cmp(&score), silver.cmp(&score)) {
-            (Ordering::Greater, _) => [Some(score), Some(gold), Some(silver)],
-            (Ordering::Equal, _) => [Some(gold), Some(silver), None],
-            (Ordering::Less, Ordering::Greater) => [Some(gold), Some(score), Some(silver)],
-            (Ordering::Less, Ordering::Equal) => [Some(gold), Some(silver), None],
-            (Ordering::Less, Ordering::Less) => [Some(gold), Some(silver), Some(score)],
-        },
-        (Some(gold), Some(silver), Some(bronze)) => {
-            match (gold.cmp(&score), silver.cmp(&score), bronze.cmp(&score)) {
-                (Ordering::Greater, _, _) => [Some(score), Some(gold), Some(silver)],
-                (Ordering::Equal, _, _) => [Some(gold), Some(silver), Some(bronze)],
-                (Ordering::Less, Ordering::Equal, _) => [Some(gold), Some(silver), Some(bronze)],
-                (Ordering::Less, Ordering::Greater, _) => [Some(gold), Some(score), Some(silver)],
-                (Ordering::Less, Ordering::Less, Ordering::Less) => {
-                    [Some(gold), Some(silver), Some(bronze)]
-                }
-                (Ordering::Less, Ordering::Less, Ordering::Equal) => {
-                    [Some(gold), Some(silver), Some(bronze)]
-                }
-                (Ordering::Less, Ordering::Less, Ordering::Greater) => {
-                    [Some(gold), Some(silver), Some(score)]
-                }
-            }
-        }
-        _ => unreachable!(),
-    }
+// The puzzles someone could be playing at the given moment, since local time
+// ranges from UTC-12 to UTC+14 there are at least two at any time.
+pub fn plausible_days(unixtime: i64) -> RangeInclusive<i64> {
+    let day_at = |offset_hours: i64| {
+        let datetime = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(unixtime as u64))
+            + chrono::Duration::hours(offset_hours);
+        day_from_date(datetime.date_naive())
+    };
+    day_at(-12) - DAY_TOLERANCE..=day_at(14) + DAY_TOLERANCE
+}
+
+// The time zone of guilds that have not configured one, set by GUILD_TIME_ZONE and Stockholm
+// time unless set
+pub fn time_zone() -> Tz {
+    std::env::var("GUILD_TIME_ZONE")
+        .ok()
+        .and_then(|time_zone| time_zone.parse().ok())
+        .unwrap_or(Tz::Europe__Stockholm)
+}
+
+pub fn local_date(unixtime: i64, time_zone: Tz) -> NaiveDate {
+    DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(unixtime as u64))
+        .with_timezone(&time_zone)
+        .date_naive()
+}
+
+// Time left until a minute past the next midnight in the time zone
+pub fn until_midnight(now: DateTime<Utc>, time_zone: Tz) -> chrono::Duration {
+    let tomorrow = now
+        .with_timezone(&time_zone)
+        .date_naive()
+        .succ_opt()
+        .unwrap();
+    let midnight = tomorrow.and_hms_opt(0, 1, 0).unwrap();
+    // Some zones skip midnight when changing to daylight saving time
+    let midnight = time_zone
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&(midnight + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap();
+    midnight.signed_duration_since(now)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn test_recalculate_scores() {
-        assert_eq!(
-            recalcualate_high_scores([None, None, None], 1),
-            [Some(1), None, None]
-        );
 
+    #[test]
+    fn test_day_date_mapping() {
+        assert_eq!(day_from_date(wordle_epoch()), 0);
         assert_eq!(
-            recalcualate_high_scores([Some(1), None, None], 1),
-            [Some(1), None, None]
+            day_from_date(NaiveDate::from_ymd_opt(2022, 12, 18).unwrap()),
+            547
         );
-
         assert_eq!(
-            recalcualate_high_scores([Some(2), None, None], 1),
-            [Some(1), Some(2), None]
-        );
-
-        assert_eq!(
-            recalcualate_high_scores([Some(1), None, None], 2),
-            [Some(1), Some(2), None]
+            date_from_day(1000),
+            NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
         );
+        for day in [0, 365, 547, 1234] {
+            assert_eq!(day_from_date(date_from_day(day)), day);
+        }
+    }
 
+    #[test]
+    fn test_local_date() {
+        let stockholm = Tz::Europe__Stockholm;
+        // 2023-01-31 23:30 UTC is already February in Stockholm
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), None], 6),
-            [Some(2), Some(4), Some(6)]
+            local_date(1675207800, stockholm),
+            NaiveDate::from_ymd_opt(2023, 2, 1).unwrap()
         );
-
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), None], 1),
-            [Some(1), Some(2), Some(4)]
+            local_date(1675207800, Tz::UTC),
+            NaiveDate::from_ymd_opt(2023, 1, 31).unwrap()
         );
-
+        // 2023-06-30 22:30 UTC is already July in summer time
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), None], 3),
-            [Some(2), Some(3), Some(4)]
+            local_date(1688164200, stockholm),
+            NaiveDate::from_ymd_opt(2023, 7, 1).unwrap()
         );
-
+        // 2023-12-31 22:30 UTC is still 23:30 on new year's eve
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), None], 2),
-            [Some(2), Some(4), None]
+            local_date(1704061800, stockholm),
+            NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()
         );
+    }
 
+    #[test]
+    fn test_until_midnight() {
+        let stockholm = Tz::Europe__Stockholm;
+        let at = |unixtime| DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(unixtime));
+        // Midnight starting 2023-03-26 in Stockholm, the day summer time begins is an hour short
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), None], 4),
-            [Some(2), Some(4), None]
+            until_midnight(at(1679785200), stockholm),
+            chrono::Duration::hours(23) + chrono::Duration::minutes(1)
         );
-
+        // Midnight starting 2023-10-29, the day summer time ends is an hour long
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), Some(6)], 5),
-            [Some(2), Some(4), Some(5)]
+            until_midnight(at(1698530400), stockholm),
+            chrono::Duration::hours(25) + chrono::Duration::minutes(1)
         );
-
+        // Midnight is skipped in Santiago when summer time begins on 2023-09-03
         assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), Some(6)], 6),
-            [Some(2), Some(4), Some(6)]
+            until_midnight(at(1693627200), Tz::America__Santiago),
+            chrono::Duration::hours(24) + chrono::Duration::minutes(1)
         );
+    }
 
-        assert_eq!(
-            recalcualate_high_scores([Some(2), Some(4), Some(6)], 7),
-            [Some(2), Some(4), Some(6)]
-        );
+    #[test]
+    fn test_plausible_days() {
+        // 2022-12-18 12:00 UTC, puzzle 547 or already 548 east of UTC+12
+        assert_eq!(plausible_days(1671364800), 546..=549);
+        // 2022-12-18 01:00 UTC, still puzzle 546 west of UTC-1
+        assert_eq!(plausible_days(1671325200), 545..=548);
+        // 2022-12-18 10:00 UTC, three puzzles are being played at once
+        assert_eq!(plausible_days(1671357600), 545..=549);
     }
 }
@@ -0,0 +1,183 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use chrono::NaiveDate;
+
+use crate::{
+    parser::{GameResult, Tile},
+    utils::{date_from_day, day_from_date, plausible_days},
+};
+
+/// Reasons for refusing a submitted result, presented to the player
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    MissingGrid,
+    RowCount {
+        score: i64,
+        rows: usize,
+    },
+    EarlyWin {
+        row: usize,
+    },
+    NotSolved,
+    SolvedFailure,
+    WrongDay {
+        day: i64,
+        date: NaiveDate,
+        expected: RangeInclusive<i64>,
+    },
+    AlreadyPlayed {
+        day: i64,
+    },
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Rejection::MissingGrid => f.write_str("resultatet saknar rutnät."),
+            Rejection::RowCount { score, rows } => {
+                write!(f, "{score} försök men rutnätet har {rows} rader.")
+            }
+            Rejection::EarlyWin { row } => write!(f, "rad {row} är redan helt grön."),
+            Rejection::NotSolved => f.write_str("sista raden är inte helt grön."),
+            Rejection::SolvedFailure => f.write_str("X men rutnätet innehåller en helt grön rad."),
+            Rejection::WrongDay {
+                day,
+                date,
+                expected,
+            } => write!(
+                f,
+                "dag {day} spelades {date}, förväntade dag {}-{}.",
+                expected.start(),
+                expected.end()
+            ),
+            Rejection::AlreadyPlayed { day } => {
+                write!(f, "dag {day} har redan ett räknat resultat.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Checks that the emoji grid agrees with the claimed score
+pub(crate) fn validate_grid(result: &GameResult) -> Result<(), Rejection> {
+    let rows = result.guesses.len();
+    if rows == 0 {
+        return Err(Rejection::MissingGrid);
+    }
+    let solved = |row: &Vec<Tile>| row.iter().all(|tile| *tile == Tile::Correct);
+    // X is stored as a score of zero but always takes all six guesses
+    let expected_rows = match result.score {
+        0 => 6,
+        score => score as usize,
+    };
+    if rows != expected_rows {
+        return Err(Rejection::RowCount {
+            score: result.score,
+            rows,
+        });
+    }
+    if result.score == 0 {
+        return match result.guesses.iter().any(solved) {
+            true => Err(Rejection::SolvedFailure),
+            false => Ok(()),
+        };
+    }
+    if let Some(row) = result.guesses[..rows - 1].iter().position(solved) {
+        return Err(Rejection::EarlyWin { row: row + 1 });
+    }
+    match solved(&result.guesses[rows - 1]) {
+        true => Ok(()),
+        false => Err(Rejection::NotSolved),
+    }
+}
+
+/// Checks that the puzzle could have been played when the message was posted,
+/// the epoch being the date puzzle zero of the game was published
+pub(crate) fn validate_day(
+    result: &GameResult,
+    epoch: NaiveDate,
+    unixtime: i64,
+) -> Result<(), Rejection> {
+    // Puzzle numbers are counted in wordle days, shifted to the epoch of the game
+    let offset = day_from_date(epoch);
+    let days = plausible_days(unixtime);
+    let expected = days.start() - offset..=days.end() - offset;
+    match expected.contains(&result.day) {
+        true => Ok(()),
+        false => Err(Rejection::WrongDay {
+            day: result.day,
+            date: date_from_day(offset + result.day),
+            expected,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_msg, utils::wordle_epoch};
+
+    #[test]
+    fn test_validate_grid() {
+        let valid = [
+            "Wordle 547 1/6\n\n🟩🟩🟩🟩🟩",
+            "Wordle 547 3/6\n\n⬛🟨⬛⬛⬛\n🟩🟩⬛🟩🟩\n🟩🟩🟩🟩🟩",
+            "Wordle 547 X/6\n\n⬛⬛⬛⬛⬛\n⬛🟨⬛⬛⬛\n🟩⬛⬛⬛⬛\n🟩🟩⬛⬛⬛\n🟩🟩🟩⬛🟩\n🟩🟩🟩⬛🟩",
+        ];
+        for msg in valid {
+            assert_eq!(validate_grid(&parse_msg(msg).unwrap()), Ok(()), "{msg}");
+        }
+
+        let invalid = [
+            ("Wordle 547 1/6", Rejection::MissingGrid),
+            (
+                "Wordle 547 1/6\n\n⬛🟨⬛⬛⬛\n🟩🟩🟩🟩🟩",
+                Rejection::RowCount { score: 1, rows: 2 },
+            ),
+            (
+                "Wordle 547 2/6\n\n🟩🟩🟩🟩🟩\n🟩🟩🟩🟩🟩",
+                Rejection::EarlyWin { row: 1 },
+            ),
+            (
+                "Wordle 547 2/6\n\n⬛🟨⬛⬛⬛\n🟩🟩⬛🟩🟩",
+                Rejection::NotSolved,
+            ),
+            (
+                "Wordle 547 X/6\n\n⬛🟨⬛⬛⬛\n🟩🟩⬛🟩🟩",
+                Rejection::RowCount { score: 0, rows: 2 },
+            ),
+            (
+                "Wordle 547 X/6\n\n⬛⬛⬛⬛⬛\n⬛🟨⬛⬛⬛\n🟩⬛⬛⬛⬛\n🟩🟩⬛⬛⬛\n🟩🟩🟩⬛🟩\n🟩🟩🟩🟩🟩",
+                Rejection::SolvedFailure,
+            ),
+        ];
+        for (msg, rejection) in invalid {
+            assert_eq!(
+                validate_grid(&parse_msg(msg).unwrap()),
+                Err(rejection),
+                "{msg}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_day() {
+        // 2022-12-18 12:00 UTC
+        let posted = 1671364800;
+        for (day, valid) in [
+            (545, false),
+            (546, true),
+            (547, true),
+            (549, true),
+            (550, false),
+        ] {
+            let result = parse_msg(&format!("Wordle {day} 1/6\n\n🟩🟩🟩🟩🟩")).unwrap();
+            assert_eq!(
+                validate_day(&result, wordle_epoch(), posted).is_ok(),
+                valid,
+                "{day}"
+            );
+        }
+    }
+}